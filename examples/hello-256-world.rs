@@ -17,12 +17,7 @@ fn main() {
     loop {
         rustbox.present();
         match rustbox.poll_event(false) {
-            Ok(rustbox::Event::KeyEvent(key)) => {
-                match key {
-                    Key::Char('q') => { break; }
-                    _ => { }
-                }
-            },
+            Ok(rustbox::Event::KeyEvent(key)) if key.key == Key::Char('q') => break,
             Err(e) => panic!("{}", e),
             _ => { }
         }
@@ -0,0 +1,29 @@
+extern crate rustbox;
+
+use std::default::Default;
+
+use rustbox::{Color, RustBox, OutputMode};
+use rustbox::Key;
+
+fn main() {
+    let mut rustbox = match RustBox::init(Default::default()) {
+        Result::Ok(v) => v,
+        Result::Err(e) => panic!("{}", e),
+    };
+    if !rustbox.supports_truecolor() {
+        drop(rustbox);
+        panic!("termbox was built without true color support");
+    }
+    rustbox.set_output_mode(OutputMode::TrueColor);
+
+    rustbox.print(1, 1, rustbox::RB_BOLD, Color::Rgb(0xff, 0x87, 0x00), Color::Default, "Hello, world!");
+    rustbox.print(1, 3, rustbox::RB_NORMAL, Color::Rgb(0x1e, 0x1e, 0x2e), Color::Rgb(0x89, 0xb4, 0xfa), "Press 'q' to quit.");
+    loop {
+        rustbox.present();
+        match rustbox.poll_event(false) {
+            Ok(rustbox::Event::KeyEvent(key)) if key.key == Key::Char('q') => break,
+            Err(e) => panic!("{}", e),
+            _ => { }
+        }
+    }
+}
@@ -4,7 +4,6 @@ use std::default::Default;
 
 use rustbox::{Color, RustBox};
 use rustbox::Key;
-use rustbox::PressedKey;
 
 fn main() {
     let rustbox = match RustBox::init(Default::default()) {
@@ -18,12 +17,7 @@ fn main() {
     loop {
         rustbox.present();
         match rustbox.poll_event(false) {
-            Ok(rustbox::Event::KeyEvent(key)) => {
-                match key.key {
-                    PressedKey::Char('q') => { break; }
-                    _ => { }
-                }
-            },
+            Ok(rustbox::Event::KeyEvent(key)) if key.key == Key::Char('q') => break,
             Err(e) => panic!("{}", e),
             _ => { }
         }
@@ -0,0 +1,650 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::{Backend, RawCell};
+use super::super::{Cell, Event, EventResult, InputMode, Key, OutputMode};
+
+/// Backend without a terminal, drawing into an in-memory screen of a fixed size.
+///
+/// Keep a `HeadlessScreen` around to inspect what was presented:
+///
+/// ```
+/// use rustbox::{Color, RustBox};
+/// use rustbox::backend::HeadlessBackend;
+/// use std::default::Default;
+///
+/// let backend = HeadlessBackend::new(20, 5);
+/// let screen = backend.screen();
+/// let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+///
+/// rb.print(1, 1, rustbox::RB_BOLD, Color::Red, Color::Default, "Hello");
+/// rb.present();
+///
+/// let cell = screen.cell(1, 1).unwrap();
+/// assert_eq!(cell.ch, 'H');
+/// assert_eq!(cell.fg, Color::Red);
+/// assert_eq!(cell.style, rustbox::RB_BOLD);
+/// assert_eq!(screen.line(1).unwrap(), " Hello              ");
+/// ```
+///
+/// Input is scripted, `poll_event` and `peek_event` return the events pushed with
+/// `HeadlessScreen::push_event` in order. Time only passes on a virtual clock, so
+/// timeouts can be tested without waiting:
+///
+/// ```
+/// use rustbox::{Event, ExtendedKey, Key, Modifiers, RustBox};
+/// use rustbox::backend::HeadlessBackend;
+/// use std::default::Default;
+/// use std::time::Duration;
+///
+/// let backend = HeadlessBackend::new(20, 5);
+/// let screen = backend.screen();
+/// let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+///
+/// let q = ExtendedKey::new(Key::Char('q'), Modifiers::new());
+/// screen.push_event(Event::ResizeEvent(40, 10));
+/// screen.push_event_after(Duration::from_millis(500), Event::KeyEvent(q));
+///
+/// match rb.poll_event(false) {
+///     Ok(Event::ResizeEvent(40, 10)) => assert_eq!(rb.width(), 40),
+///     _ => panic!(),
+/// }
+/// match rb.peek_event(Duration::from_millis(100), false) {
+///     Ok(Event::NoEvent) => (),
+///     _ => panic!(),
+/// }
+/// match rb.peek_event(Duration::from_millis(400), false) {
+///     Ok(Event::KeyEvent(key)) => assert_eq!(key.key, Key::Char('q')),
+///     _ => panic!(),
+/// }
+/// assert_eq!(screen.elapsed(), Duration::from_millis(500));
+/// ```
+pub struct HeadlessBackend {
+    screen: HeadlessScreen,
+}
+
+/// Shared view of the screen of a `HeadlessBackend`.
+#[derive(Clone)]
+pub struct HeadlessScreen {
+    inner: Arc<Mutex<Grid>>,
+}
+
+struct Grid {
+    width: usize,
+    height: usize,
+    // Cells as drawn by the application
+    back: Vec<RawCell>,
+    // Cells as of the last `present`
+    front: Vec<RawCell>,
+    // Combining marks following the character of each cell, in `back` and `front`
+    back_marks: Vec<String>,
+    front_marks: Vec<String>,
+    cursor: (isize, isize),
+    cursor_visible: bool,
+    mouse: bool,
+    input_mode: InputMode,
+    output_mode: OutputMode,
+    // Scripted events with the virtual time they become available at
+    events: VecDeque<(Duration, Event)>,
+    // Time of the last scripted event
+    script_end: Duration,
+    // Virtual time, advanced by waiting for events
+    clock: Duration,
+}
+
+impl HeadlessBackend {
+    pub fn new(width: usize, height: usize) -> HeadlessBackend {
+        let blank = RawCell::blank(OutputMode::Normal);
+        HeadlessBackend {
+            screen: HeadlessScreen {
+                inner: Arc::new(Mutex::new(Grid {
+                    width,
+                    height,
+                    back: vec![blank; width * height],
+                    front: vec![blank; width * height],
+                    back_marks: vec![String::new(); width * height],
+                    front_marks: vec![String::new(); width * height],
+                    cursor: (-1, -1),
+                    cursor_visible: true,
+                    mouse: false,
+                    input_mode: InputMode::Esc,
+                    output_mode: OutputMode::Normal,
+                    events: VecDeque::new(),
+                    script_end: Duration::from_millis(0),
+                    clock: Duration::from_millis(0),
+                })),
+            },
+        }
+    }
+
+    pub fn screen(&self) -> HeadlessScreen {
+        self.screen.clone()
+    }
+
+    fn grid(&self) -> ::std::sync::MutexGuard<'_, Grid> {
+        self.screen.inner.lock().unwrap()
+    }
+
+    /// Takes the next scripted event available within `timeout`, moving the clock
+    /// forward to when it happened or by the whole timeout if none did.
+    fn next_event(&self, timeout: Option<Duration>, raw: bool) -> EventResult {
+        let mut grid = self.grid();
+        let now = grid.clock;
+        let due = match grid.events.front() {
+            Some(&(at, _)) => match timeout {
+                Some(timeout) => at <= now + timeout,
+                None => true,
+            },
+            None => false,
+        };
+        if !due {
+            grid.clock = now + timeout.unwrap_or(Duration::from_millis(0));
+            return Ok(Event::NoEvent);
+        }
+
+        // `unwrap` is safe here because `due` is only set for a pending event.
+        let (at, event) = grid.events.pop_front().unwrap();
+        if at > now {
+            grid.clock = at;
+        }
+        match event {
+            Event::ResizeEvent(w, h) => {
+                drop(grid);
+                self.screen.resize(w.max(0) as usize, h.max(0) as usize);
+                Ok(event)
+            },
+            Event::KeyEvent(key) if raw => {
+                let ch = match key.key {
+                    Key::Char(c) if key.raw_ch == 0 => c as u32,
+                    _ => key.raw_ch,
+                };
+                let code = if key.raw_key != 0 { key.raw_key } else { key.code() };
+                Ok(Event::KeyEventRaw(key.raw_emod | key.modifiers.as_meta(), code, ch))
+            },
+            event => Ok(event),
+        }
+    }
+}
+
+impl HeadlessScreen {
+    pub fn width(&self) -> usize {
+        self.inner.lock().unwrap().width
+    }
+
+    pub fn height(&self) -> usize {
+        self.inner.lock().unwrap().height
+    }
+
+    /// The cell at `x`, `y` as of the last `present`.
+    pub fn cell(&self, x: usize, y: usize) -> Option<Cell> {
+        let grid = self.inner.lock().unwrap();
+        if x >= grid.width || y >= grid.height {
+            return None;
+        }
+        Some(Cell::from_raw(grid.front[y * grid.width + x], grid.output_mode))
+    }
+
+    /// The characters of row `y` as of the last `present`, with the combining marks
+    /// kept in their cells. The right halves of wide characters are left out, so the
+    /// text reads as it appears on screen.
+    pub fn line(&self, y: usize) -> Option<String> {
+        let grid = self.inner.lock().unwrap();
+        if y >= grid.height {
+            return None;
+        }
+        let row = &grid.front[y * grid.width..(y + 1) * grid.width];
+        let marks = &grid.front_marks[y * grid.width..(y + 1) * grid.width];
+        let mut line = String::new();
+        for (cell, marks) in row.iter().zip(marks).filter(|&(cell, _)| cell.ch != 0) {
+            line.push(Cell::from_raw(*cell, grid.output_mode).ch);
+            line.push_str(marks);
+        }
+        Some(line)
+    }
+
+    /// All rows as of the last `present`.
+    pub fn lines(&self) -> Vec<String> {
+        (0..self.height()).filter_map(|y| self.line(y)).collect()
+    }
+
+    /// Position of the cursor, if it is shown.
+    pub fn cursor(&self) -> Option<(usize, usize)> {
+        let grid = self.inner.lock().unwrap();
+        match grid.cursor {
+            (x, y) if grid.cursor_visible && x >= 0 && y >= 0 => Some((x as usize, y as usize)),
+            _ => None,
+        }
+    }
+
+    /// Schedules `event` at the same time as the previously scripted one.
+    pub fn push_event(&self, event: Event) {
+        self.push_event_after(Duration::from_millis(0), event);
+    }
+
+    /// Schedules `event` `delay` after the previously scripted one.
+    pub fn push_event_after(&self, delay: Duration, event: Event) {
+        let mut grid = self.inner.lock().unwrap();
+        let at = grid.script_end.max(grid.clock) + delay;
+        grid.script_end = at;
+        grid.events.push_back((at, event));
+    }
+
+    /// Number of scripted events not yet returned.
+    pub fn pending_events(&self) -> usize {
+        self.inner.lock().unwrap().events.len()
+    }
+
+    /// Time passed on the virtual clock.
+    pub fn elapsed(&self) -> Duration {
+        self.inner.lock().unwrap().clock
+    }
+
+    pub fn mouse_enabled(&self) -> bool {
+        self.inner.lock().unwrap().mouse
+    }
+
+    /// Changes the size of the screen, keeping the cells that still fit.
+    pub fn resize(&self, width: usize, height: usize) {
+        let mut grid = self.inner.lock().unwrap();
+        let blank = RawCell::blank(grid.output_mode);
+        let mut back = vec![blank; width * height];
+        let mut front = vec![blank; width * height];
+        let mut back_marks = vec![String::new(); width * height];
+        let mut front_marks = vec![String::new(); width * height];
+        for y in 0..height.min(grid.height) {
+            for x in 0..width.min(grid.width) {
+                let (i, j) = (y * width + x, y * grid.width + x);
+                back[i] = grid.back[j];
+                front[i] = grid.front[j];
+                back_marks[i] = grid.back_marks[j].clone();
+                front_marks[i] = grid.front_marks[j].clone();
+            }
+        }
+        grid.width = width;
+        grid.height = height;
+        grid.back = back;
+        grid.front = front;
+        grid.back_marks = back_marks;
+        grid.front_marks = front_marks;
+    }
+}
+
+impl Backend for HeadlessBackend {
+    fn width(&self) -> usize {
+        self.grid().width
+    }
+
+    fn height(&self) -> usize {
+        self.grid().height
+    }
+
+    fn clear(&self) {
+        let mut grid = self.grid();
+        let blank = RawCell::blank(grid.output_mode);
+        for cell in grid.back.iter_mut() {
+            *cell = blank;
+        }
+        for marks in grid.back_marks.iter_mut() {
+            marks.clear();
+        }
+    }
+
+    fn clear_screen(&self) {
+        let mut grid = self.grid();
+        let blank = RawCell::blank(grid.output_mode);
+        for cell in grid.front.iter_mut() {
+            *cell = blank;
+        }
+        for marks in grid.front_marks.iter_mut() {
+            marks.clear();
+        }
+    }
+
+    fn present(&self) {
+        let mut grid = self.grid();
+        grid.front = grid.back.clone();
+        grid.front_marks = grid.back_marks.clone();
+    }
+
+    fn set_cursor(&self, x: isize, y: isize) {
+        self.grid().cursor = (x, y);
+    }
+
+    fn hide_cursor(&self) {
+        self.grid().cursor_visible = false;
+    }
+
+    fn show_cursor(&self) {
+        self.grid().cursor_visible = true;
+    }
+
+    fn change_cell(&self, x: usize, y: usize, ch: u32, fg: u32, bg: u32) {
+        let mut grid = self.grid();
+        if x < grid.width && y < grid.height {
+            let i = y * grid.width + x;
+            grid.back[i] = RawCell { ch, fg, bg };
+            grid.back_marks[i].clear();
+        }
+    }
+
+    fn change_cluster(&self, x: usize, y: usize, ch: u32, marks: &str, fg: u32, bg: u32) {
+        let mut grid = self.grid();
+        if x < grid.width && y < grid.height {
+            let i = y * grid.width + x;
+            grid.back[i] = RawCell { ch, fg, bg };
+            grid.back_marks[i] = marks.to_string();
+        }
+    }
+
+    fn get_cell(&self, x: usize, y: usize) -> Option<RawCell> {
+        let grid = self.grid();
+        if x < grid.width && y < grid.height {
+            Some(grid.back[y * grid.width + x])
+        } else {
+            None
+        }
+    }
+
+    /// Returns `Event::NoEvent` instead of blocking once the script has run out.
+    fn poll_event(&self, raw: bool) -> EventResult {
+        self.next_event(None, raw)
+    }
+
+    fn peek_event(&self, timeout: Duration, raw: bool) -> EventResult {
+        self.next_event(Some(timeout), raw)
+    }
+
+    fn enable_mouse(&self) {
+        self.grid().mouse = true;
+    }
+
+    fn disable_mouse(&self) {
+        self.grid().mouse = false;
+    }
+
+    fn select_input_mode(&self, mode: InputMode) -> InputMode {
+        let mut grid = self.grid();
+        if mode != InputMode::Current {
+            grid.input_mode = mode;
+            grid.mouse = mode == InputMode::EscMouse || mode == InputMode::AltMouse;
+        }
+        grid.input_mode
+    }
+
+    fn select_output_mode(&self, mode: OutputMode) -> OutputMode {
+        let mut grid = self.grid();
+        if grid.output_mode != mode {
+            // attributes are encoded differently in every mode
+            let blank = RawCell::blank(mode);
+            grid.output_mode = mode;
+            grid.back = vec![blank; grid.width * grid.height];
+            grid.front = grid.back.clone();
+            grid.back_marks = vec![String::new(); grid.width * grid.height];
+            grid.front_marks = grid.back_marks.clone();
+        }
+        mode
+    }
+
+    fn supports_truecolor(&self) -> bool {
+        true
+    }
+
+    fn suspend(&self) {}
+
+    fn resume(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HeadlessBackend, HeadlessScreen};
+    use std::default::Default;
+    use std::time::Duration;
+    use {Cell, Color, Event, ExtendedKey, Key, Modifiers, Mouse, OutputMode, RustBox, RB_BOLD, RB_NORMAL};
+
+    fn headless(width: usize, height: usize) -> (RustBox, HeadlessScreen) {
+        let backend = HeadlessBackend::new(width, height);
+        let screen = backend.screen();
+        (RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap(), screen)
+    }
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn asking_for_truecolor_keeps_what_was_drawn() {
+        let (rb, screen) = headless(3, 1);
+        rb.print(0, 0, RB_NORMAL, Color::Red, Color::Default, "abc");
+        assert!(rb.supports_truecolor());
+        rb.present();
+        assert_eq!(screen.line(0).unwrap(), "abc");
+        assert_eq!(screen.cell(0, 0).unwrap().fg, Color::Red);
+        assert_eq!(rb.output_mode(), OutputMode::Normal);
+    }
+
+    #[test]
+    fn lines_show_what_was_presented() {
+        let (rb, screen) = headless(6, 2);
+        rb.print(0, 0, RB_NORMAL, Color::Default, Color::Default, "ab");
+        rb.print(1, 1, RB_NORMAL, Color::Default, Color::Default, "日x");
+        assert_eq!(screen.lines(), vec!["      ", "      "]);
+        rb.present();
+        assert_eq!(screen.line(0).unwrap(), "ab    ");
+        // the right half of the wide character is left out
+        assert_eq!(screen.line(1).unwrap(), " 日x  ");
+        assert_eq!(screen.lines(), vec!["ab    ", " 日x  "]);
+        assert_eq!(screen.line(2), None);
+    }
+
+    #[test]
+    fn cursor_is_only_reported_when_shown() {
+        let (rb, screen) = headless(4, 4);
+        assert_eq!(screen.cursor(), None);
+        rb.set_cursor(2, 3);
+        assert_eq!(screen.cursor(), Some((2, 3)));
+        rb.hide_cursor();
+        assert_eq!(screen.cursor(), None);
+        rb.show_cursor();
+        assert_eq!(screen.cursor(), Some((2, 3)));
+        rb.set_cursor(-1, 0);
+        assert_eq!(screen.cursor(), None);
+    }
+
+    #[test]
+    fn resize_keeps_the_cells_that_fit() {
+        let (rb, screen) = headless(3, 2);
+        rb.print(0, 0, RB_NORMAL, Color::Default, Color::Default, "abc");
+        rb.print(0, 1, RB_NORMAL, Color::Default, Color::Default, "def");
+        rb.present();
+
+        screen.resize(2, 3);
+        assert_eq!((screen.width(), screen.height()), (2, 3));
+        assert_eq!((rb.width(), rb.height()), (2, 3));
+        assert_eq!(screen.lines(), vec!["ab", "de", "  "]);
+
+        screen.resize(4, 1);
+        assert_eq!(screen.lines(), vec!["ab  "]);
+        assert_eq!(screen.cell(3, 0).unwrap().ch, ' ');
+        assert!(screen.cell(0, 1).is_none());
+    }
+
+    #[test]
+    fn virtual_clock_follows_the_script() {
+        let (rb, screen) = headless(4, 4);
+        screen.push_event_after(ms(300), Event::ResizeEvent(5, 5));
+        screen.push_event_after(ms(200), Event::ResizeEvent(6, 6));
+        assert_eq!(screen.pending_events(), 2);
+
+        // the first event is due after 300ms, a shorter wait times out
+        match rb.peek_event(ms(100), false) {
+            Ok(Event::NoEvent) => (),
+            _ => panic!(),
+        }
+        assert_eq!(screen.elapsed(), ms(100));
+
+        // waiting returns as soon as the event happens
+        match rb.peek_event(ms(1000), false) {
+            Ok(Event::ResizeEvent(5, 5)) => (),
+            _ => panic!(),
+        }
+        assert_eq!(screen.elapsed(), ms(300));
+        assert_eq!((screen.width(), screen.height()), (5, 5));
+
+        // delays count from the previous event, polling waits as long as needed
+        match rb.poll_event(false) {
+            Ok(Event::ResizeEvent(6, 6)) => (),
+            _ => panic!(),
+        }
+        assert_eq!(screen.elapsed(), ms(500));
+
+        // out of events, polling doesn't block and the clock stands still
+        assert_eq!(screen.pending_events(), 0);
+        match rb.poll_event(false) {
+            Ok(Event::NoEvent) => (),
+            _ => panic!(),
+        }
+        assert_eq!(screen.elapsed(), ms(500));
+
+        // events pushed later are scheduled from the current time
+        screen.push_event_after(ms(50), Event::ResizeEvent(7, 7));
+        match rb.peek_event(ms(50), false) {
+            Ok(Event::ResizeEvent(7, 7)) => (),
+            _ => panic!(),
+        }
+        assert_eq!(screen.elapsed(), ms(550));
+    }
+
+    #[test]
+    fn scripted_events_come_in_order() {
+        let (rb, screen) = headless(4, 4);
+        let a = ExtendedKey::new(Key::Char('a'), Modifiers::new());
+        let b = ExtendedKey::new(Key::Char('b'), Modifiers::new());
+        screen.push_event(Event::KeyEvent(a));
+        screen.push_event(Event::MouseEvent(Mouse::Left, 1, 2));
+        screen.push_event_after(ms(40), Event::KeyEvent(b));
+        screen.push_event(Event::ResizeEvent(8, 3));
+
+        // events pushed together are all due at once
+        match rb.peek_event(ms(0), false) {
+            Ok(Event::KeyEvent(key)) => assert_eq!(key.key, Key::Char('a')),
+            _ => panic!(),
+        }
+        match rb.peek_event(ms(0), false) {
+            Ok(Event::MouseEvent(Mouse::Left, 1, 2)) => (),
+            _ => panic!(),
+        }
+        assert_eq!(screen.elapsed(), ms(0));
+
+        // the delayed one isn't there before its time
+        match rb.peek_event(ms(39), false) {
+            Ok(Event::NoEvent) => (),
+            _ => panic!(),
+        }
+        match rb.peek_event(ms(1), false) {
+            Ok(Event::KeyEvent(key)) => assert_eq!(key.key, Key::Char('b')),
+            _ => panic!(),
+        }
+        // and the one pushed right after it comes along at the same time
+        match rb.peek_event(ms(0), false) {
+            Ok(Event::ResizeEvent(8, 3)) => (),
+            _ => panic!(),
+        }
+        assert_eq!(screen.elapsed(), ms(40));
+        assert_eq!(screen.pending_events(), 0);
+    }
+
+    #[test]
+    fn peek_timeouts_advance_the_clock() {
+        let (rb, screen) = headless(4, 4);
+        for _ in 0..3 {
+            match rb.peek_event(ms(250), false) {
+                Ok(Event::NoEvent) => (),
+                _ => panic!(),
+            }
+        }
+        assert_eq!(screen.elapsed(), ms(750));
+
+        // a delay counts from the current time once the script has run out
+        screen.push_event_after(ms(100), Event::ResizeEvent(2, 2));
+        match rb.peek_event(ms(99), false) {
+            Ok(Event::NoEvent) => (),
+            _ => panic!(),
+        }
+        assert_eq!(screen.elapsed(), ms(849));
+        match rb.peek_event(ms(99), false) {
+            Ok(Event::ResizeEvent(2, 2)) => (),
+            _ => panic!(),
+        }
+        assert_eq!(screen.elapsed(), ms(850));
+    }
+
+    #[test]
+    fn raw_key_events() {
+        let (rb, screen) = headless(4, 4);
+        let ctrl = Modifiers::new_all(true, false, false);
+        screen.push_event(Event::KeyEvent(ExtendedKey::new(Key::Char('x'), Modifiers::new())));
+        screen.push_event(Event::KeyEvent(ExtendedKey::new(Key::Char('c'), ctrl)));
+        match rb.poll_event(true) {
+            Ok(Event::KeyEventRaw(0, 0, ch)) => assert_eq!(ch, 'x' as u32),
+            _ => panic!(),
+        }
+        match rb.poll_event(true) {
+            Ok(Event::KeyEventRaw(_, code, _)) => assert_eq!(code, 0x03),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn cells_read_back_as_drawn() {
+        let (rb, screen) = headless(4, 2);
+        rb.print(1, 0, RB_BOLD, Color::Red, Color::Blue, "hi");
+        let hi = Cell { ch: 'h', fg: Color::Red, bg: Color::Blue, style: RB_BOLD };
+        assert_eq!(rb.get_cell(1, 0), Some(hi));
+        assert_eq!(rb.get_cell(4, 0), None);
+        let (fg, bg) = rb.cell_attributes(RB_BOLD, Color::Red, Color::Blue);
+        let raw = rb.backend.get_cell(1, 0).unwrap();
+        assert_eq!((raw.ch, raw.fg, raw.bg), ('h' as u32, fg, bg));
+        // drawn but not presented yet
+        assert_eq!(screen.cell(1, 0).unwrap().ch, ' ');
+    }
+
+    #[test]
+    fn cell_buffer_edits_are_written_back() {
+        let (rb, screen) = headless(4, 2);
+        rb.print(0, 0, RB_NORMAL, Color::Default, Color::Default, "ab");
+        {
+            let mut buffer = rb.cell_buffer();
+            assert_eq!((buffer.width(), buffer.height()), (4, 2));
+            assert_eq!(buffer.get(1, 0).unwrap().ch, 'b');
+            {
+                let cell = buffer.get_mut(0, 1).unwrap();
+                cell.ch = 'z';
+                cell.fg = Color::Green;
+            }
+            buffer.row_mut(0).unwrap()[3].ch = 'd';
+            // nothing is written back while the buffer is alive
+            assert_eq!(rb.backend.get_cell(0, 1).unwrap().ch, ' ' as u32);
+        }
+        assert_eq!(rb.get_cell(0, 1).unwrap().fg, Color::Green);
+        rb.present();
+        assert_eq!(screen.lines(), vec!["ab d", "z   "]);
+        assert_eq!(screen.cell(0, 1).unwrap().fg, Color::Green);
+    }
+
+    #[test]
+    fn copy_region_moves_cells() {
+        let (rb, screen) = headless(4, 3);
+        rb.print(0, 0, RB_NORMAL, Color::Default, Color::Default, "abcd");
+        rb.print(0, 1, RB_NORMAL, Color::Default, Color::Default, "efgh");
+        {
+            let mut buffer = rb.cell_buffer();
+            // overlapping, the source is read before anything is written
+            buffer.copy_region(0, 0, 3, 2, 1, 1);
+            // partly off the buffer
+            buffer.copy_region(2, 0, 4, 1, 3, 0);
+        }
+        rb.present();
+        assert_eq!(screen.lines(), vec!["abcc", "eabc", " efg"]);
+    }
+}
@@ -0,0 +1,157 @@
+//! Terminal backends behind `RustBox`.
+//!
+//! `RustBox` only keeps track of modes and locking, the actual terminal work is
+//! done by a `Backend`. The termbox backend (cargo feature `termbox`, on by
+//! default) wraps the termbox C library, the native backend (cargo feature
+//! `native`) talks to the terminal directly in Rust. The headless backend keeps
+//! the screen in memory for tests.
+
+use std::time::Duration;
+
+use super::{EventResult, Event, InitError, InputMode, OutputMode, Style, TRUECOLOR_DEFAULT};
+use super::style::TB_ATTRIB;
+
+mod headless;
+#[cfg(feature = "termbox")]
+mod termbox;
+#[cfg(feature = "native")]
+mod native;
+
+pub use self::headless::{HeadlessBackend, HeadlessScreen};
+#[cfg(feature = "termbox")]
+pub use self::termbox::TermboxBackend;
+#[cfg(feature = "native")]
+pub use self::native::NativeBackend;
+
+/// An event as reported by termbox, before it is unpacked into an `Event`.
+#[derive(Clone, Copy, Debug)]
+pub struct RawEvent {
+    pub etype: u8,
+    pub meta: u8,
+    pub key: u16,
+    pub ch: u32,
+    pub w: i16,
+    pub h: i16,
+    pub x: i16,
+    pub y: i16,
+}
+
+/// A cell of the back buffer with encoded attributes, see `RustBox::change_cell`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RawCell {
+    pub ch: u32,
+    pub fg: u32,
+    pub bg: u32,
+}
+
+impl RawCell {
+    /// An empty cell in default colors.
+    pub fn blank(mode: OutputMode) -> RawCell {
+        match mode {
+            OutputMode::TrueColor => RawCell { ch: ' ' as u32, fg: TRUECOLOR_DEFAULT, bg: TRUECOLOR_DEFAULT },
+            _ => RawCell { ch: ' ' as u32, fg: 0, bg: 0 },
+        }
+    }
+}
+
+pub const NIL_RAW_EVENT: RawEvent = RawEvent { etype: 0, key: 0, ch: 0, meta: 0, w: 0, h: 0, x: 0, y: 0 };
+
+/// Everything `RustBox` needs from a terminal.
+///
+/// Cell attributes are encoded the same way for every backend, see
+/// `RustBox::change_cell`. `RustBox` serializes calls with its input and output
+/// locks, so a backend only has to cope with one reader and one writer at a time.
+pub trait Backend: Send + Sync {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+
+    /// Clears the back buffer.
+    fn clear(&self);
+    /// Clears the terminal itself, forcing the next `present` to redraw everything.
+    fn clear_screen(&self);
+    /// Synchronizes the terminal with the back buffer.
+    fn present(&self);
+
+    /// Moves the cursor, negative coordinates hide it.
+    fn set_cursor(&self, x: isize, y: isize);
+    fn hide_cursor(&self);
+    fn show_cursor(&self);
+
+    fn change_cell(&self, x: usize, y: usize, ch: u32, fg: u32, bg: u32);
+    /// Like `change_cell` for a grapheme cluster without a precomposed form, `ch`
+    /// followed by the combining `marks`. Backends that hold a single character
+    /// per cell only keep `ch`.
+    fn change_cluster(&self, x: usize, y: usize, ch: u32, _marks: &str, fg: u32, bg: u32) {
+        self.change_cell(x, y, ch, fg, bg)
+    }
+    /// The cell at `x`, `y` in the back buffer, `None` when out of bounds.
+    fn get_cell(&self, x: usize, y: usize) -> Option<RawCell>;
+
+    fn poll_event(&self, raw: bool) -> EventResult;
+    fn peek_event(&self, timeout: Duration, raw: bool) -> EventResult;
+
+    fn enable_mouse(&self);
+    fn disable_mouse(&self);
+
+    /// Selects an input mode and returns the one in effect afterwards.
+    fn select_input_mode(&self, mode: InputMode) -> InputMode;
+    /// Selects an output mode and returns the one in effect afterwards, which
+    /// differs from `mode` if the backend can't support it.
+    fn select_output_mode(&self, mode: OutputMode) -> OutputMode;
+    /// Whether `select_output_mode` can switch to `OutputMode::TrueColor`. This
+    /// only reports what the backend found out when it started, without
+    /// touching the terminal.
+    fn supports_truecolor(&self) -> bool {
+        false
+    }
+
+    /// Text attributes the terminal can show. The rest are replaced by a
+    /// fallback before they reach `change_cell`.
+    fn supported_styles(&self) -> Style {
+        TB_ATTRIB
+    }
+
+    /// Gives the terminal back to the shell until `resume` is called.
+    fn suspend(&self);
+    fn resume(&self);
+}
+
+/// The backend of `OutputMode::NoOutput`, which never touches the terminal.
+pub struct NullBackend;
+
+impl Backend for NullBackend {
+    fn width(&self) -> usize { 0 }
+    fn height(&self) -> usize { 0 }
+    fn clear(&self) {}
+    fn clear_screen(&self) {}
+    fn present(&self) {}
+    fn set_cursor(&self, _x: isize, _y: isize) {}
+    fn hide_cursor(&self) {}
+    fn show_cursor(&self) {}
+    fn change_cell(&self, _x: usize, _y: usize, _ch: u32, _fg: u32, _bg: u32) {}
+    fn get_cell(&self, _x: usize, _y: usize) -> Option<RawCell> { None }
+    fn poll_event(&self, _raw: bool) -> EventResult { Ok(Event::NoEvent) }
+    fn peek_event(&self, _timeout: Duration, _raw: bool) -> EventResult { Ok(Event::NoEvent) }
+    fn enable_mouse(&self) {}
+    fn disable_mouse(&self) {}
+    fn select_input_mode(&self, mode: InputMode) -> InputMode { mode }
+    fn select_output_mode(&self, mode: OutputMode) -> OutputMode { mode }
+    fn suspend(&self) {}
+    fn resume(&self) {}
+}
+
+/// The backend `RustBox::init` uses, picked by cargo feature.
+#[cfg(feature = "termbox")]
+pub fn default_backend() -> Result<Box<dyn Backend>, InitError> {
+    TermboxBackend::init().map(|b| Box::new(b) as Box<dyn Backend>)
+}
+
+#[cfg(all(feature = "native", not(feature = "termbox")))]
+pub fn default_backend() -> Result<Box<dyn Backend>, InitError> {
+    NativeBackend::init().map(|b| Box::new(b) as Box<dyn Backend>)
+}
+
+#[cfg(not(any(feature = "termbox", feature = "native")))]
+pub fn default_backend() -> Result<Box<dyn Backend>, InitError> {
+    Err(InitError::UnsupportedTerminal)
+}
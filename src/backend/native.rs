@@ -0,0 +1,609 @@
+use std::char;
+use std::env;
+use std::fmt::Write as FmtWrite;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, Read, Write};
+use std::mem;
+use std::os::raw::c_int;
+use std::os::unix::io::AsRawFd;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use libc;
+use unicode_width::UnicodeWidthChar;
+
+use super::{Backend, RawCell};
+use super::super::{Color, Event, EventError, EventResult, ExtendedKey, InitError, InputMode, Mouse, OutputMode, Style};
+use super::super::{RB_BOLD, RB_ITALIC, RB_UNDERLINE, RB_REVERSE, RB_BLINK, RB_DIM};
+use super::super::{RB_STRIKETHROUGH, RB_OVERLINE, RB_DOUBLE_UNDERLINE};
+use super::super::keyboard::parse_key;
+
+/// How long a lone ESC waits for the rest of an escape sequence.
+const ESC_TIMEOUT_MS: u64 = 25;
+
+/// Marks cells of the front buffer whose content on the terminal is unknown.
+const DIRTY_CELL: RawCell = RawCell { ch: 0xffff_ffff, fg: 0, bg: 0 };
+
+// Set by the SIGWINCH handler, there is only one terminal to resize.
+static RESIZED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_sigwinch(_: c_int) {
+    RESIZED.store(true, Ordering::SeqCst);
+}
+
+/// Backend written in Rust, driving an xterm compatible terminal through
+/// `/dev/tty` with escape sequences.
+pub struct NativeBackend {
+    tty: File,
+    // Terminal settings and SIGWINCH handler to restore on suspend and drop
+    original: libc::termios,
+    original_sigwinch: Mutex<Option<libc::sigaction>>,
+    // Whether the terminal announced 24-bit color in `COLORTERM`
+    truecolor: bool,
+    output: Mutex<Screen>,
+    input: Mutex<Input>,
+}
+
+struct Screen {
+    width: usize,
+    height: usize,
+    // Cells as drawn by the application
+    back: Vec<RawCell>,
+    // Cells as currently shown by the terminal
+    front: Vec<RawCell>,
+    // Combining marks following the character of each cell, in `back` and `front`
+    back_marks: Vec<String>,
+    front_marks: Vec<String>,
+    cursor: (isize, isize),
+    cursor_visible: bool,
+    mouse: bool,
+    output_mode: OutputMode,
+}
+
+struct Input {
+    buf: Vec<u8>,
+    input_mode: InputMode,
+}
+
+impl NativeBackend {
+    pub fn init() -> Result<NativeBackend, InitError> {
+        let tty = OpenOptions::new().read(true).write(true).open("/dev/tty")
+            .map_err(|_| InitError::FailedToOpenTTy)?;
+
+        let mut original: libc::termios = unsafe { mem::zeroed() };
+        if unsafe { libc::tcgetattr(tty.as_raw_fd(), &mut original) } != 0 {
+            return Err(InitError::UnsupportedTerminal);
+        }
+
+        let backend = NativeBackend {
+            tty,
+            original,
+            original_sigwinch: Mutex::new(None),
+            truecolor: announces_truecolor(),
+            output: Mutex::new(Screen {
+                width: 0,
+                height: 0,
+                back: vec![],
+                front: vec![],
+                back_marks: vec![],
+                front_marks: vec![],
+                cursor: (-1, -1),
+                cursor_visible: true,
+                mouse: false,
+                output_mode: OutputMode::Normal,
+            }),
+            input: Mutex::new(Input {
+                buf: vec![],
+                input_mode: InputMode::Esc,
+            }),
+        };
+        backend.enter();
+        {
+            let mut screen = backend.output.lock().unwrap();
+            let (width, height) = backend.size();
+            screen.resize(width, height);
+        }
+        Ok(backend)
+    }
+
+    /// Puts the terminal into raw mode on the alternate screen.
+    fn enter(&self) {
+        let mut raw = self.original;
+        unsafe {
+            libc::cfmakeraw(&mut raw);
+            libc::tcsetattr(self.tty.as_raw_fd(), libc::TCSAFLUSH, &raw);
+
+            let mut action: libc::sigaction = mem::zeroed();
+            action.sa_sigaction = on_sigwinch as extern "C" fn(c_int) as libc::sighandler_t;
+            libc::sigemptyset(&mut action.sa_mask);
+            let mut previous: libc::sigaction = mem::zeroed();
+            if libc::sigaction(libc::SIGWINCH, &action, &mut previous) == 0 {
+                *self.original_sigwinch.lock().unwrap() = Some(previous);
+            }
+        }
+        let mut screen = self.output.lock().unwrap();
+        let mut out = String::from("\x1b[?1049h\x1b[?25l\x1b[0m\x1b[2J");
+        if screen.mouse {
+            out.push_str(MOUSE_ON);
+        }
+        self.write(&out);
+        screen.invalidate();
+    }
+
+    /// Restores the terminal as it was before `enter`.
+    fn leave(&self) {
+        let screen = self.output.lock().unwrap();
+        let mut out = String::from("\x1b[0m\x1b[?25h\x1b[?1049l");
+        if screen.mouse {
+            out.push_str(MOUSE_OFF);
+        }
+        self.write(&out);
+        unsafe {
+            libc::tcsetattr(self.tty.as_raw_fd(), libc::TCSAFLUSH, &self.original);
+            if let Some(previous) = self.original_sigwinch.lock().unwrap().take() {
+                libc::sigaction(libc::SIGWINCH, &previous, ::std::ptr::null_mut());
+            }
+        }
+    }
+
+    fn size(&self) -> (usize, usize) {
+        let mut size: libc::winsize = unsafe { mem::zeroed() };
+        if unsafe { libc::ioctl(self.tty.as_raw_fd(), libc::TIOCGWINSZ, &mut size) } != 0 {
+            return (80, 24);
+        }
+        (size.ws_col as usize, size.ws_row as usize)
+    }
+
+    fn write(&self, out: &str) {
+        // There is nobody to report a broken terminal to
+        let _ = (&self.tty).write_all(out.as_bytes());
+        let _ = (&self.tty).flush();
+    }
+
+    /// Reads the next event, waiting at most `timeout` or forever if it's `None`.
+    fn read_event(&self, timeout: Option<Duration>, raw: bool) -> EventResult {
+        let mut input = self.input.lock().unwrap();
+        let deadline = timeout.map(|t| Instant::now() + t);
+        let mut flush = false;
+        loop {
+            if RESIZED.swap(false, Ordering::SeqCst) {
+                let (width, height) = self.size();
+                self.output.lock().unwrap().resize(width, height);
+                return Ok(Event::ResizeEvent(width as i16, height as i16));
+            }
+            if let Some(event) = input.next_event(raw, flush) {
+                return Ok(event);
+            }
+
+            // An ESC without anything after it is most likely the Esc key
+            let pending_esc = !input.buf.is_empty();
+            let wait = match deadline {
+                _ if pending_esc => Some(Duration::from_millis(ESC_TIMEOUT_MS)),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    Some(if deadline > now { deadline - now } else { Duration::from_millis(0) })
+                },
+                None => None,
+            };
+            let timeout_ms = match wait {
+                Some(w) => (w.as_secs() * 1000 + w.subsec_nanos() as u64 / 1000000) as c_int,
+                None => -1,
+            };
+
+            let mut fds = libc::pollfd { fd: self.tty.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+            match unsafe { libc::poll(&mut fds, 1, timeout_ms) } {
+                // interrupted, most likely by SIGWINCH
+                n if n < 0 => continue,
+                0 => {
+                    if pending_esc {
+                        flush = true;
+                        continue;
+                    }
+                    return Ok(Event::NoEvent);
+                },
+                _ => {
+                    let mut chunk = [0u8; 256];
+                    match (&self.tty).read(&mut chunk) {
+                        // the terminal hung up, polling again would return at once
+                        Ok(0) => return Err(EventError::Hangup),
+                        Ok(n) => {
+                            input.buf.extend_from_slice(&chunk[..n]);
+                            flush = false;
+                        },
+                        Err(ref e) if e.kind() == ErrorKind::Interrupted || e.kind() == ErrorKind::WouldBlock => (),
+                        Err(e) => return Err(EventError::Unknown(e.raw_os_error().unwrap_or(0) as isize)),
+                    }
+                },
+            }
+        }
+    }
+}
+
+impl Screen {
+    fn resize(&mut self, width: usize, height: usize) {
+        let blank = self.blank();
+        let mut back = vec![blank; width * height];
+        let mut back_marks = vec![String::new(); width * height];
+        for y in 0..height.min(self.height) {
+            for x in 0..width.min(self.width) {
+                back[y * width + x] = self.back[y * self.width + x];
+                back_marks[y * width + x] = self.back_marks[y * self.width + x].clone();
+            }
+        }
+        self.width = width;
+        self.height = height;
+        self.back = back;
+        self.back_marks = back_marks;
+        self.invalidate();
+    }
+
+    fn invalidate(&mut self) {
+        self.front = vec![DIRTY_CELL; self.width * self.height];
+        self.front_marks = vec![String::new(); self.width * self.height];
+    }
+
+    fn blank(&self) -> RawCell {
+        RawCell::blank(self.output_mode)
+    }
+
+    fn clear(&mut self) {
+        let blank = self.blank();
+        for cell in self.back.iter_mut() {
+            *cell = blank;
+        }
+        for marks in self.back_marks.iter_mut() {
+            marks.clear();
+        }
+    }
+
+    /// Escape sequences that bring the terminal up to date with the back buffer.
+    fn render(&mut self) -> String {
+        let mut out = String::from("\x1b[?25l");
+        let mut attributes = None;
+        let mut position = None;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let i = y * self.width + x;
+                let cell = self.back[i];
+                if cell == self.front[i] && self.back_marks[i] == self.front_marks[i] {
+                    continue;
+                }
+                self.front[i] = cell;
+                self.front_marks[i].clone_from(&self.back_marks[i]);
+                // the right half of a wide character is drawn along with the left
+                if cell.ch == 0 {
+                    continue;
+                }
+                if position != Some((x, y)) {
+                    let _ = write!(out, "\x1b[{};{}H", y + 1, x + 1);
+                }
+                if attributes != Some((cell.fg, cell.bg)) {
+                    out.push_str(&sgr(cell.fg, cell.bg, self.output_mode));
+                    attributes = Some((cell.fg, cell.bg));
+                }
+                let width = match char::from_u32(cell.ch) {
+                    Some(c) if c >= ' ' => {
+                        out.push(c);
+                        out.push_str(&self.back_marks[i]);
+                        c.width().unwrap_or(1)
+                    },
+                    _ => {
+                        out.push(' ');
+                        1
+                    },
+                };
+                position = Some((x + width, y));
+            }
+        }
+        out.push_str("\x1b[0m");
+        let (cx, cy) = self.cursor;
+        if self.cursor_visible && cx >= 0 && cy >= 0 {
+            let _ = write!(out, "\x1b[{};{}H\x1b[?25h", cy + 1, cx + 1);
+        }
+        out
+    }
+}
+
+/// SGR parameter of each text attribute.
+const SGR_ATTRIBUTES: [(Style, u8); 9] = [
+    (RB_BOLD, 1),
+    (RB_DIM, 2),
+    (RB_ITALIC, 3),
+    (RB_UNDERLINE, 4),
+    (RB_BLINK, 5),
+    (RB_REVERSE, 7),
+    (RB_STRIKETHROUGH, 9),
+    (RB_DOUBLE_UNDERLINE, 21),
+    (RB_OVERLINE, 53),
+];
+
+/// Select graphic rendition sequence for encoded cell attributes.
+fn sgr(fg: u32, bg: u32, mode: OutputMode) -> String {
+    let mut out = String::from("\x1b[0");
+    let sty = Style::from_attributes(fg, bg, mode);
+    for &(flag, code) in SGR_ATTRIBUTES.iter() {
+        if sty.contains(flag) {
+            let _ = write!(out, ";{}", code);
+        }
+    }
+    push_color(&mut out, Color::from_attribute(fg, mode), 30);
+    push_color(&mut out, Color::from_attribute(bg, mode), 40);
+    out.push('m');
+    out
+}
+
+/// Appends the SGR parameters of a color, `base` being 30 for foreground and 40
+/// for background.
+fn push_color(out: &mut String, color: Color, base: u8) {
+    let _ = match color {
+        Color::Default => write!(out, ";{}", base + 9),
+        Color::Black => write!(out, ";{}", base),
+        Color::Byte(b) => write!(out, ";{};5;{}", base + 8, b),
+        Color::Rgb(r, g, b) => write!(out, ";{};2;{};{};{}", base + 8, r, g, b),
+        // the other named colors are palette entries 1 to 7
+        named => write!(out, ";{}", base + named.as_256color() as u8),
+    };
+}
+
+const MOUSE_ON: &str = "\x1b[?1000h\x1b[?1002h\x1b[?1006h";
+const MOUSE_OFF: &str = "\x1b[?1006l\x1b[?1002l\x1b[?1000l";
+
+/// Terminals with 24-bit color say so in `COLORTERM`, there is no escape
+/// sequence to ask them.
+fn announces_truecolor() -> bool {
+    match env::var("COLORTERM") {
+        Ok(value) => value == "truecolor" || value == "24bit",
+        Err(_) => false,
+    }
+}
+
+impl Input {
+    /// Takes the next complete event off the input buffer. With `flush` an
+    /// incomplete escape sequence is given up on and its ESC reported as a key.
+    fn next_event(&mut self, raw: bool, flush: bool) -> Option<Event> {
+        if self.buf.is_empty() {
+            return None;
+        }
+        if self.buf.starts_with(b"\x1b[<") || self.buf.starts_with(b"\x1b[M") {
+            if let Some((event, len)) = parse_mouse(&self.buf) {
+                self.buf.drain(..len);
+                return Some(event);
+            } else if !flush {
+                return None;
+            }
+        }
+
+        let esc_mode = !matches!(self.input_mode, InputMode::Alt | InputMode::AltMouse);
+        let parsed = if self.buf[0] != 0x1b {
+            parse_key(&self.buf)
+        } else if self.buf.len() == 1 && !flush {
+            None
+        } else if flush || (esc_mode && self.buf[1] != b'[' && self.buf[1] != b'O') {
+            // In `InputMode::Esc` an ESC that doesn't start a sequence is a key of its own
+            parse_key(&self.buf[..1])
+        } else {
+            parse_key(&self.buf)
+        };
+
+        match parsed {
+            Some((key, len)) => {
+                self.buf.drain(..len);
+                Some(key_event(key, raw))
+            },
+            // half of a UTF-8 character will never be complete
+            None if flush => {
+                let first = self.buf.remove(0);
+                Some(Event::KeyEventRaw(0, 0, first as u32))
+            },
+            None => None,
+        }
+    }
+}
+
+fn key_event(key: ExtendedKey, raw: bool) -> Event {
+    let mut key = key;
+    key.raw_key = key.code();
+    if raw {
+        Event::KeyEventRaw(key.raw_emod, key.raw_key, key.raw_ch)
+    } else {
+        Event::KeyEvent(key)
+    }
+}
+
+/// Parses SGR (`ESC[<b;x;yM`) and X10 (`ESC[Mbxy`) mouse reports.
+fn parse_mouse(buf: &[u8]) -> Option<(Event, usize)> {
+    let (button, x, y, release, len) = if buf[2] == b'<' {
+        let end = buf.iter().position(|&b| b == b'M' || b == b'm')?;
+        let params: Vec<u32> = String::from_utf8_lossy(&buf[3..end])
+            .split(';').map(|p| p.parse().unwrap_or(0)).collect();
+        if params.len() < 3 {
+            return Some((Event::NoEvent, end + 1));
+        }
+        (params[0], params[1], params[2], buf[end] == b'm', end + 1)
+    } else {
+        if buf.len() < 6 {
+            return None;
+        }
+        let button = buf[3].wrapping_sub(32) as u32;
+        (button, buf[4].wrapping_sub(32) as u32, buf[5].wrapping_sub(32) as u32, button & 3 == 3, 6)
+    };
+
+    let mouse = if button & 64 != 0 {
+        if button & 1 == 0 { Mouse::WheelUp } else { Mouse::WheelDown }
+    } else if release {
+        Mouse::Release
+    } else if button & 32 != 0 {
+        // motion while a button is held, reported in drag tracking mode
+        Mouse::Drag
+    } else {
+        match button & 3 {
+            0 => Mouse::Left,
+            1 => Mouse::Middle,
+            2 => Mouse::Right,
+            _ => Mouse::Release,
+        }
+    };
+    // terminals count from 1
+    Some((Event::MouseEvent(mouse, x.saturating_sub(1) as i16, y.saturating_sub(1) as i16), len))
+}
+
+impl Backend for NativeBackend {
+    fn width(&self) -> usize {
+        self.output.lock().unwrap().width
+    }
+
+    fn height(&self) -> usize {
+        self.output.lock().unwrap().height
+    }
+
+    fn clear(&self) {
+        self.output.lock().unwrap().clear();
+    }
+
+    fn clear_screen(&self) {
+        let mut screen = self.output.lock().unwrap();
+        self.write("\x1b[0m\x1b[2J");
+        screen.invalidate();
+    }
+
+    fn present(&self) {
+        let out = self.output.lock().unwrap().render();
+        self.write(&out);
+    }
+
+    fn set_cursor(&self, x: isize, y: isize) {
+        self.output.lock().unwrap().cursor = (x, y);
+    }
+
+    fn hide_cursor(&self) {
+        self.output.lock().unwrap().cursor_visible = false;
+        self.write("\x1b[?25l");
+    }
+
+    fn show_cursor(&self) {
+        self.output.lock().unwrap().cursor_visible = true;
+    }
+
+    fn change_cell(&self, x: usize, y: usize, ch: u32, fg: u32, bg: u32) {
+        let mut screen = self.output.lock().unwrap();
+        if x < screen.width && y < screen.height {
+            let i = y * screen.width + x;
+            screen.back[i] = RawCell { ch, fg, bg };
+            screen.back_marks[i].clear();
+        }
+    }
+
+    fn change_cluster(&self, x: usize, y: usize, ch: u32, marks: &str, fg: u32, bg: u32) {
+        let mut screen = self.output.lock().unwrap();
+        if x < screen.width && y < screen.height {
+            let i = y * screen.width + x;
+            screen.back[i] = RawCell { ch, fg, bg };
+            screen.back_marks[i] = marks.to_string();
+        }
+    }
+
+    fn get_cell(&self, x: usize, y: usize) -> Option<RawCell> {
+        let screen = self.output.lock().unwrap();
+        if x < screen.width && y < screen.height {
+            Some(screen.back[y * screen.width + x])
+        } else {
+            None
+        }
+    }
+
+    fn poll_event(&self, raw: bool) -> EventResult {
+        loop {
+            match self.read_event(None, raw) {
+                Ok(Event::NoEvent) => continue,
+                result => return result,
+            }
+        }
+    }
+
+    fn peek_event(&self, timeout: Duration, raw: bool) -> EventResult {
+        self.read_event(Some(timeout), raw)
+    }
+
+    fn enable_mouse(&self) {
+        self.output.lock().unwrap().mouse = true;
+        self.write(MOUSE_ON);
+    }
+
+    fn disable_mouse(&self) {
+        self.output.lock().unwrap().mouse = false;
+        self.write(MOUSE_OFF);
+    }
+
+    fn select_input_mode(&self, mode: InputMode) -> InputMode {
+        if mode == InputMode::Current {
+            return self.input.lock().unwrap().input_mode;
+        }
+        self.input.lock().unwrap().input_mode = mode;
+        match mode {
+            InputMode::EscMouse | InputMode::AltMouse => self.enable_mouse(),
+            _ => self.disable_mouse(),
+        }
+        mode
+    }
+
+    fn select_output_mode(&self, mode: OutputMode) -> OutputMode {
+        let mode = if mode == OutputMode::TrueColor && !self.truecolor { OutputMode::EightBit } else { mode };
+        let mut screen = self.output.lock().unwrap();
+        if screen.output_mode != mode {
+            screen.output_mode = mode;
+            // attributes are encoded differently in every mode
+            screen.clear();
+            screen.invalidate();
+        }
+        mode
+    }
+
+    fn supports_truecolor(&self) -> bool {
+        self.truecolor
+    }
+
+    fn suspend(&self) {
+        self.leave();
+    }
+
+    fn resume(&self) {
+        self.enter();
+        let (width, height) = self.size();
+        self.output.lock().unwrap().resize(width, height);
+    }
+}
+
+impl Drop for NativeBackend {
+    fn drop(&mut self) {
+        self.leave();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_mouse;
+    use {Event, Mouse};
+
+    fn mouse(buf: &[u8]) -> Option<(Mouse, i16, i16)> {
+        match parse_mouse(buf) {
+            Some((Event::MouseEvent(mouse, x, y), _)) => Some((mouse, x, y)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn sgr_reports() {
+        assert_eq!(mouse(b"\x1b[<0;3;2M"), Some((Mouse::Left, 2, 1)));
+        assert_eq!(mouse(b"\x1b[<2;3;2M"), Some((Mouse::Right, 2, 1)));
+        assert_eq!(mouse(b"\x1b[<0;3;2m"), Some((Mouse::Release, 2, 1)));
+        assert_eq!(mouse(b"\x1b[<65;3;2M"), Some((Mouse::WheelDown, 2, 1)));
+    }
+
+    #[test]
+    fn motion_is_not_a_press() {
+        assert_eq!(mouse(b"\x1b[<32;4;2M"), Some((Mouse::Drag, 3, 1)));
+        assert_eq!(mouse(b"\x1b[M@$\""), Some((Mouse::Drag, 3, 1)));
+        assert_eq!(mouse(b"\x1b[M #\""), Some((Mouse::Left, 2, 1)));
+    }
+}
@@ -0,0 +1,264 @@
+use std::os::raw::c_int;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use num_traits::FromPrimitive;
+use termbox;
+
+use super::{Backend, RawCell, RawEvent};
+use super::super::{unpack_event, EventResult, InitError, InputMode, OutputMode, Style};
+use super::super::{RB_BOLD, RB_ITALIC, RB_UNDERLINE, RB_REVERSE, RB_BLINK, RB_DIM, RB_NORMAL};
+
+const NIL_TERMBOX_EVENT: termbox::RawEvent = termbox::RawEvent { etype: 0, key: 0, ch: 0, meta: 0, w: 0, h: 0, x: 0, y: 0 };
+
+// Attribute bits of termbox2 cells with 16 bit attributes
+const TB_BOLD: u32 = 0x0100;
+const TB_UNDERLINE: u32 = 0x0200;
+const TB_REVERSE: u32 = 0x0400;
+const TB_ITALIC: u32 = 0x0800;
+const TB_BLINK: u32 = 0x1000;
+const TB_DIM: u32 = 0x8000;
+
+/// Where termbox2 keeps each style in a 16 or 256 color cell.
+///
+/// `TB_STRIKEOUT`, `TB_UNDERLINE_2` and `TB_OVERLINE` only exist when termbox2
+/// is built with 64 bit attributes, which don't fit in a cell of termbox-sys.
+/// Strikethrough, double underline and overline are left out of
+/// `supported_styles` and drawn with their fallbacks, see `Style::fallback`.
+const TERMBOX_ATTRIBUTES: [(Style, u32); 6] = [
+    (RB_BOLD, TB_BOLD),
+    (RB_UNDERLINE, TB_UNDERLINE),
+    (RB_REVERSE, TB_REVERSE),
+    (RB_ITALIC, TB_ITALIC),
+    (RB_BLINK, TB_BLINK),
+    (RB_DIM, TB_DIM),
+];
+
+/// Color bits of a 16 or 256 color cell.
+const TERMBOX_COLOR: u32 = 0x00ff;
+
+/// Backend on top of the termbox C library.
+///
+/// Termbox keeps global state, so there can only be one of these at a time. Its
+/// cells hold a single character, so combining marks without a precomposed form
+/// are dropped.
+pub struct TermboxBackend {
+    // Cells are only translated for termbox outside of true color mode
+    output_mode: Mutex<OutputMode>,
+    // Whether termbox was built with true color support, probed once at init
+    truecolor: bool,
+}
+
+impl TermboxBackend {
+    pub fn init() -> Result<TermboxBackend, InitError> {
+        match unsafe { termbox::tb_init() } {
+            0 => Ok(TermboxBackend {
+                output_mode: Mutex::new(OutputMode::Normal),
+                truecolor: TermboxBackend::probe_truecolor(),
+            }),
+            // `unwrap` is safe here because FromPrimitive for InitError only returns `Some`.
+            res => Err(FromPrimitive::from_isize(res as isize).unwrap()),
+        }
+    }
+
+    fn unpack(rc: c_int, ev: &termbox::RawEvent, raw: bool) -> EventResult {
+        let ev = RawEvent {
+            etype: ev.etype,
+            meta: ev.meta,
+            key: ev.key,
+            ch: ev.ch,
+            w: ev.w,
+            h: ev.h,
+            x: ev.x,
+            y: ev.y,
+        };
+        unpack_event(rc, &ev, raw)
+    }
+
+    /// Selects true color and goes back to normal mode, before anything is drawn.
+    fn probe_truecolor() -> bool {
+        unsafe {
+            let selected = termbox::tb_select_output_mode(OutputMode::TrueColor as c_int);
+            termbox::tb_select_output_mode(OutputMode::Normal as c_int);
+            selected == OutputMode::TrueColor as c_int
+        }
+    }
+
+    /// Styles termbox has attribute bits for.
+    fn styles() -> Style {
+        TERMBOX_ATTRIBUTES.iter().fold(RB_NORMAL, |sty, &(flag, _)| sty | flag)
+    }
+
+    /// Turns a 16 or 256 color cell attribute into termbox's encoding.
+    fn to_termbox(attr: u32) -> u32 {
+        let sty = Style::from_bits_truncate(attr);
+        TERMBOX_ATTRIBUTES.iter()
+            .filter(|&&(flag, _)| sty.contains(flag))
+            .fold(attr & TERMBOX_COLOR, |attr, &(_, bit)| attr | bit)
+    }
+
+    /// Turns a 16 or 256 color termbox cell attribute back into ours.
+    fn from_termbox(attr: u32) -> u32 {
+        let sty = TERMBOX_ATTRIBUTES.iter()
+            .filter(|&&(_, bit)| attr & bit != 0)
+            .fold(RB_NORMAL, |sty, &(flag, _)| sty | flag);
+        attr & TERMBOX_COLOR | sty.bits()
+    }
+}
+
+impl Backend for TermboxBackend {
+    fn width(&self) -> usize {
+        unsafe { termbox::tb_width() as usize }
+    }
+
+    fn height(&self) -> usize {
+        unsafe { termbox::tb_height() as usize }
+    }
+
+    fn clear(&self) {
+        unsafe { termbox::tb_clear_buffer() }
+    }
+
+    fn clear_screen(&self) {
+        unsafe { termbox::tb_clear_screen() }
+    }
+
+    fn present(&self) {
+        unsafe { termbox::tb_render() }
+    }
+
+    fn set_cursor(&self, x: isize, y: isize) {
+        unsafe { termbox::tb_set_cursor(x as c_int, y as c_int) }
+    }
+
+    fn hide_cursor(&self) {
+        unsafe { termbox::tb_hide_cursor() }
+    }
+
+    fn show_cursor(&self) {
+        unsafe { termbox::tb_show_cursor() }
+    }
+
+    fn change_cell(&self, x: usize, y: usize, ch: u32, fg: u32, bg: u32) {
+        let (fg, bg) = match *self.output_mode.lock().unwrap() {
+            OutputMode::TrueColor => (fg, bg),
+            _ => (TermboxBackend::to_termbox(fg), TermboxBackend::to_termbox(bg)),
+        };
+        // Cells are 32 bits wide only when termbox is built with true color support.
+        let cell = termbox::RawCell{
+            ch,
+            fg: fg as _,
+            bg: bg as _,
+        };
+        unsafe {
+            termbox::tb_cell(x as i32, y as i32, &cell);
+        }
+    }
+
+    fn get_cell(&self, x: usize, y: usize) -> Option<RawCell> {
+        let (width, height) = (self.width(), self.height());
+        if x >= width || y >= height {
+            return None;
+        }
+        unsafe {
+            let buffer = termbox::tb_cell_buffer();
+            if buffer.is_null() {
+                return None;
+            }
+            let cell = *buffer.add(y * width + x);
+            let (fg, bg) = match *self.output_mode.lock().unwrap() {
+                OutputMode::TrueColor => (cell.fg as u32, cell.bg as u32),
+                _ => (TermboxBackend::from_termbox(cell.fg as u32), TermboxBackend::from_termbox(cell.bg as u32)),
+            };
+            Some(RawCell { ch: cell.ch, fg, bg })
+        }
+    }
+
+    fn poll_event(&self, raw: bool) -> EventResult {
+        let mut ev = NIL_TERMBOX_EVENT;
+        let rc = unsafe {
+            termbox::tb_poll_event(&mut ev)
+        };
+        TermboxBackend::unpack(rc, &ev, raw)
+    }
+
+    fn peek_event(&self, timeout: Duration, raw: bool) -> EventResult {
+        let mut ev = NIL_TERMBOX_EVENT;
+        let rc = unsafe {
+            termbox::tb_peek_event(&mut ev, (timeout.as_secs() * 1000 + timeout.subsec_nanos() as u64 / 1000000) as c_int)
+        };
+        TermboxBackend::unpack(rc, &ev, raw)
+    }
+
+    fn enable_mouse(&self) {
+        unsafe { termbox::tb_enable_mouse() }
+    }
+
+    fn disable_mouse(&self) {
+        unsafe { termbox::tb_disable_mouse() }
+    }
+
+    fn select_input_mode(&self, mode: InputMode) -> InputMode {
+        let selected = unsafe {
+            termbox::tb_select_input_mode(mode as c_int)
+        };
+        InputMode::from_code(selected).unwrap_or(mode)
+    }
+
+    fn select_output_mode(&self, mode: OutputMode) -> OutputMode {
+        // termbox was built without true color support
+        let mode = if mode == OutputMode::TrueColor && !self.truecolor { OutputMode::EightBit } else { mode };
+        unsafe {
+            termbox::tb_select_output_mode(mode as c_int);
+        }
+        *self.output_mode.lock().unwrap() = mode;
+        mode
+    }
+
+    fn supports_truecolor(&self) -> bool {
+        self.truecolor
+    }
+
+    fn supported_styles(&self) -> Style {
+        TermboxBackend::styles()
+    }
+
+    fn suspend(&self) {
+        unsafe { termbox::tb_shutdown() }
+    }
+
+    fn resume(&self) {
+        unsafe {
+            termbox::tb_init();
+        }
+    }
+}
+
+impl Drop for TermboxBackend {
+    fn drop(&mut self) {
+        unsafe {
+            termbox::tb_shutdown();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TermboxBackend, TB_BOLD, TB_DIM, TB_ITALIC};
+    use {RB_BOLD, RB_DIM, RB_DOUBLE_UNDERLINE, RB_ITALIC, RB_OVERLINE, RB_STRIKETHROUGH, RB_UNDERLINE};
+
+    #[test]
+    fn styles_take_termbox2_bits() {
+        let attr = 0x01 | (RB_BOLD | RB_ITALIC | RB_DIM).bits();
+        assert_eq!(TermboxBackend::to_termbox(attr), 0x01 | TB_BOLD | TB_ITALIC | TB_DIM);
+        assert_eq!(TermboxBackend::from_termbox(TermboxBackend::to_termbox(attr)), attr);
+    }
+
+    #[test]
+    fn styles_without_bits_fall_back() {
+        let supported = TermboxBackend::styles();
+        assert!(supported.contains(RB_ITALIC | RB_DIM));
+        assert_eq!((RB_STRIKETHROUGH | RB_OVERLINE).fallback(supported), RB_DIM | RB_UNDERLINE);
+        assert_eq!(RB_DOUBLE_UNDERLINE.fallback(supported), RB_UNDERLINE);
+    }
+}
@@ -0,0 +1,216 @@
+//! Line and border glyphs, and how crossing lines join up.
+
+/// The look of lines and borders drawn with `RustBox::draw_hline` and friends.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Border {
+    Single,
+    Double,
+    /// Single lines with rounded corners.
+    Rounded,
+    Heavy,
+    /// `-`, `|` and `+`, for terminals without box drawing characters.
+    Ascii,
+}
+
+impl Default for Border {
+    fn default() -> Self {
+        Border::Single
+    }
+}
+
+/// Sides of a cell a line leaves from, in the order up, right, down, left.
+pub type Sides = [bool; 4];
+
+pub const HORIZONTAL: Sides = [false, true, false, true];
+pub const VERTICAL: Sides = [true, false, true, false];
+pub const TOP_LEFT: Sides = [false, true, true, false];
+pub const TOP_RIGHT: Sides = [false, false, true, true];
+pub const BOTTOM_LEFT: Sides = [true, true, false, false];
+pub const BOTTOM_RIGHT: Sides = [true, false, false, true];
+
+// Line weights of a glyph's sides
+const NONE: u8 = 0;
+const LIGHT: u8 = 1;
+const HEAVY: u8 = 2;
+const DOUBLE: u8 = 3;
+
+/// Box drawing glyphs with the weight of the line on each side, up, right,
+/// down and left.
+const GLYPHS: [(char, [u8; 4]); 51] = [
+    ('─', [NONE, LIGHT, NONE, LIGHT]),
+    ('│', [LIGHT, NONE, LIGHT, NONE]),
+    ('┌', [NONE, LIGHT, LIGHT, NONE]),
+    ('┐', [NONE, NONE, LIGHT, LIGHT]),
+    ('└', [LIGHT, LIGHT, NONE, NONE]),
+    ('┘', [LIGHT, NONE, NONE, LIGHT]),
+    ('├', [LIGHT, LIGHT, LIGHT, NONE]),
+    ('┤', [LIGHT, NONE, LIGHT, LIGHT]),
+    ('┬', [NONE, LIGHT, LIGHT, LIGHT]),
+    ('┴', [LIGHT, LIGHT, NONE, LIGHT]),
+    ('┼', [LIGHT, LIGHT, LIGHT, LIGHT]),
+    ('━', [NONE, HEAVY, NONE, HEAVY]),
+    ('┃', [HEAVY, NONE, HEAVY, NONE]),
+    ('┏', [NONE, HEAVY, HEAVY, NONE]),
+    ('┓', [NONE, NONE, HEAVY, HEAVY]),
+    ('┗', [HEAVY, HEAVY, NONE, NONE]),
+    ('┛', [HEAVY, NONE, NONE, HEAVY]),
+    ('┣', [HEAVY, HEAVY, HEAVY, NONE]),
+    ('┫', [HEAVY, NONE, HEAVY, HEAVY]),
+    ('┳', [NONE, HEAVY, HEAVY, HEAVY]),
+    ('┻', [HEAVY, HEAVY, NONE, HEAVY]),
+    ('╋', [HEAVY, HEAVY, HEAVY, HEAVY]),
+    ('═', [NONE, DOUBLE, NONE, DOUBLE]),
+    ('║', [DOUBLE, NONE, DOUBLE, NONE]),
+    ('╔', [NONE, DOUBLE, DOUBLE, NONE]),
+    ('╗', [NONE, NONE, DOUBLE, DOUBLE]),
+    ('╚', [DOUBLE, DOUBLE, NONE, NONE]),
+    ('╝', [DOUBLE, NONE, NONE, DOUBLE]),
+    ('╠', [DOUBLE, DOUBLE, DOUBLE, NONE]),
+    ('╣', [DOUBLE, NONE, DOUBLE, DOUBLE]),
+    ('╦', [NONE, DOUBLE, DOUBLE, DOUBLE]),
+    ('╩', [DOUBLE, DOUBLE, NONE, DOUBLE]),
+    ('╬', [DOUBLE, DOUBLE, DOUBLE, DOUBLE]),
+    ('╒', [NONE, DOUBLE, LIGHT, NONE]),
+    ('╓', [NONE, LIGHT, DOUBLE, NONE]),
+    ('╕', [NONE, NONE, LIGHT, DOUBLE]),
+    ('╖', [NONE, NONE, DOUBLE, LIGHT]),
+    ('╘', [LIGHT, DOUBLE, NONE, NONE]),
+    ('╙', [DOUBLE, LIGHT, NONE, NONE]),
+    ('╛', [LIGHT, NONE, NONE, DOUBLE]),
+    ('╜', [DOUBLE, NONE, NONE, LIGHT]),
+    ('╞', [LIGHT, DOUBLE, LIGHT, NONE]),
+    ('╟', [DOUBLE, LIGHT, DOUBLE, NONE]),
+    ('╡', [LIGHT, NONE, LIGHT, DOUBLE]),
+    ('╢', [DOUBLE, NONE, DOUBLE, LIGHT]),
+    ('╤', [NONE, DOUBLE, LIGHT, DOUBLE]),
+    ('╥', [NONE, LIGHT, DOUBLE, LIGHT]),
+    ('╧', [LIGHT, DOUBLE, NONE, DOUBLE]),
+    ('╨', [DOUBLE, LIGHT, NONE, LIGHT]),
+    ('╪', [LIGHT, DOUBLE, LIGHT, DOUBLE]),
+    ('╫', [DOUBLE, LIGHT, DOUBLE, LIGHT]),
+];
+
+/// Rounded corners, which join like the light ones.
+const ROUNDED_CORNERS: [(char, char); 4] = [('┌', '╭'), ('┐', '╮'), ('└', '╰'), ('┘', '╯')];
+
+impl Border {
+    /// The glyph for a line leaving a cell on `sides`.
+    pub fn glyph(&self, sides: Sides) -> char {
+        self.join(' ', sides)
+    }
+
+    /// The glyph for a line leaving a cell on `sides` drawn over `existing`.
+    /// Lines already in the cell are kept, so crossing lines turn into T and
+    /// cross junctions.
+    pub fn join(&self, existing: char, sides: Sides) -> char {
+        if *self == Border::Ascii {
+            return join_ascii(existing, sides);
+        }
+
+        let weight = match *self {
+            Border::Heavy => HEAVY,
+            Border::Double => DOUBLE,
+            _ => LIGHT,
+        };
+        let (old, sides) = match weights(existing) {
+            Some(old) => (old, sides),
+            // nothing to join, so a line end is drawn like the rest of the line
+            None => ([NONE; 4], whole_line(sides)),
+        };
+        let mut joined = [NONE; 4];
+        for i in 0..4 {
+            joined[i] = if sides[i] { weight } else { old[i] };
+        }
+        // Mixes without a glyph of their own take on the weight of the new line.
+        let glyph = glyph_of(joined).or_else(|| {
+            let mut uniform = joined;
+            for w in uniform.iter_mut().filter(|w| **w != NONE) {
+                *w = weight;
+            }
+            glyph_of(uniform)
+        }).unwrap_or('+');
+
+        match ROUNDED_CORNERS.iter().find(|&&(square, _)| square == glyph) {
+            Some(&(_, rounded)) if *self == Border::Rounded => rounded,
+            _ => glyph,
+        }
+    }
+}
+
+/// Extends a line end, leaving the cell on a single side, to both sides.
+fn whole_line(sides: Sides) -> Sides {
+    match sides.iter().filter(|&&side| side).count() {
+        1 if sides[0] || sides[2] => VERTICAL,
+        1 => HORIZONTAL,
+        _ => sides,
+    }
+}
+
+fn weights(ch: char) -> Option<[u8; 4]> {
+    let ch = match ROUNDED_CORNERS.iter().find(|&&(_, rounded)| rounded == ch) {
+        Some(&(square, _)) => square,
+        None => ch,
+    };
+    GLYPHS.iter().find(|&&(glyph, _)| glyph == ch).map(|&(_, weights)| weights)
+}
+
+fn glyph_of(weights: [u8; 4]) -> Option<char> {
+    GLYPHS.iter().find(|&&(_, w)| w == weights).map(|&(glyph, _)| glyph)
+}
+
+fn join_ascii(existing: char, sides: Sides) -> char {
+    let old = match existing {
+        '-' => HORIZONTAL,
+        '|' => VERTICAL,
+        '+' => [true; 4],
+        _ => [false; 4],
+    };
+    let horizontal = sides[1] || sides[3] || old[1] || old[3];
+    let vertical = sides[0] || sides[2] || old[0] || old[2];
+    match (horizontal, vertical) {
+        (true, false) => '-',
+        (false, true) => '|',
+        _ => '+',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Border, HORIZONTAL, VERTICAL, TOP_LEFT, TOP_RIGHT, BOTTOM_LEFT};
+
+    #[test]
+    fn glyphs() {
+        assert_eq!(Border::Single.glyph(TOP_LEFT), '┌');
+        assert_eq!(Border::Double.glyph(HORIZONTAL), '═');
+        assert_eq!(Border::Heavy.glyph(VERTICAL), '┃');
+        assert_eq!(Border::Rounded.glyph(TOP_RIGHT), '╮');
+        assert_eq!(Border::Ascii.glyph(BOTTOM_LEFT), '+');
+        // a line end is drawn like the rest of its line
+        assert_eq!(Border::Single.glyph([false, true, false, false]), '─');
+    }
+
+    #[test]
+    fn crossing_lines_join() {
+        assert_eq!(Border::Single.join('─', VERTICAL), '┼');
+        assert_eq!(Border::Single.join('│', [false, true, false, false]), '├');
+        assert_eq!(Border::Single.join('┐', TOP_LEFT), '┬');
+        assert_eq!(Border::Single.join('x', VERTICAL), '│');
+        assert_eq!(Border::Ascii.join('-', VERTICAL), '+');
+        assert_eq!(Border::Ascii.join('|', VERTICAL), '|');
+    }
+
+    #[test]
+    fn mixed_weights_join() {
+        assert_eq!(Border::Double.join('│', HORIZONTAL), '╪');
+        // mixes without a glyph of their own take on the weight of the new line
+        assert_eq!(Border::Heavy.join('─', VERTICAL), '╋');
+        assert_eq!(Border::Double.join('━', VERTICAL), '╬');
+    }
+
+    #[test]
+    fn rounded_corners_join_like_light_ones() {
+        assert_eq!(Border::Single.join('╭', BOTTOM_LEFT), '├');
+        assert_eq!(Border::Rounded.join('─', TOP_LEFT), '┬');
+        assert_eq!(Border::Rounded.join(' ', BOTTOM_LEFT), '╰');
+    }
+}
@@ -0,0 +1,118 @@
+//! Bulk access to the cells of the screen, see `RustBox::cell_buffer`.
+
+use std::ops::{Deref, DerefMut};
+use std::sync::{LockResult, MutexGuard};
+
+use super::{Cell, RustBox};
+
+/// A copy of the back buffer, indexed row by row like a slice.
+///
+/// Holds the output lock of the `RustBox` it came from and writes changed cells
+/// back when dropped.
+pub struct CellBuffer<'a> {
+    rb: &'a RustBox,
+    _lock: LockResult<MutexGuard<'a, ()>>,
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+    original: Vec<Cell>,
+}
+
+impl<'a> CellBuffer<'a> {
+    pub(crate) fn new(rb: &'a RustBox, lock: LockResult<MutexGuard<'a, ()>>,
+                      width: usize, height: usize, cells: Vec<Cell>) -> CellBuffer<'a> {
+        CellBuffer {
+            rb,
+            _lock: lock,
+            width,
+            height,
+            original: cells.clone(),
+            cells,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Option<&Cell> {
+        if x < self.width && y < self.height {
+            self.cells.get(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut Cell> {
+        if x < self.width && y < self.height {
+            self.cells.get_mut(y * self.width + x)
+        } else {
+            None
+        }
+    }
+
+    pub fn row(&self, y: usize) -> Option<&[Cell]> {
+        if y < self.height {
+            Some(&self.cells[y * self.width..(y + 1) * self.width])
+        } else {
+            None
+        }
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> Option<&mut [Cell]> {
+        if y < self.height {
+            Some(&mut self.cells[y * self.width..(y + 1) * self.width])
+        } else {
+            None
+        }
+    }
+
+    /// Copies the `width` x `height` region at `x`, `y` so its top left corner
+    /// lands on `to_x`, `to_y`. Parts falling off the buffer are dropped and
+    /// overlapping regions are handled.
+    pub fn copy_region(&mut self, x: usize, y: usize, width: usize, height: usize,
+                       to_x: usize, to_y: usize) {
+        let region: Vec<Vec<Cell>> = (y..(y + height).min(self.height))
+            .map(|row| {
+                let start = row * self.width;
+                self.cells[start + x.min(self.width)..start + (x + width).min(self.width)].to_vec()
+            })
+            .collect();
+        for (dy, cells) in region.into_iter().enumerate() {
+            for (dx, cell) in cells.into_iter().enumerate() {
+                if let Some(target) = self.get_mut(to_x + dx, to_y + dy) {
+                    *target = cell;
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Deref for CellBuffer<'a> {
+    type Target = [Cell];
+
+    fn deref(&self) -> &[Cell] {
+        &self.cells
+    }
+}
+
+impl<'a> DerefMut for CellBuffer<'a> {
+    fn deref_mut(&mut self) -> &mut [Cell] {
+        &mut self.cells
+    }
+}
+
+impl<'a> Drop for CellBuffer<'a> {
+    fn drop(&mut self) {
+        for (i, cell) in self.cells.iter().enumerate() {
+            if *cell != self.original[i] {
+                let raw = self.rb.encode_cell(cell);
+                self.rb.backend.change_cell(i % self.width, i / self.width, raw.ch, raw.fg, raw.bg);
+            }
+        }
+    }
+}
@@ -0,0 +1,328 @@
+use super::OutputMode;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Byte(u16),
+    Rgb(u8, u8, u8),
+    Default,
+}
+
+/// Marks `Color::Default` in a true color cell, since every 24-bit value is a valid color.
+pub const TRUECOLOR_DEFAULT: u32 = 0x8000_0000;
+
+/// Termbox's `TB_DEFAULT`, the code of `Color::Default` in 16 and 256 color
+/// cells. It takes the place of palette entry 0, so black is always sent as
+/// entry 16, which is black as well.
+const PALETTE_DEFAULT: u16 = 0x00;
+
+/// Palette entry black is sent as.
+const PALETTE_BLACK: u16 = 0x10;
+
+/// Standard xterm values for the 16 system colors.
+const SYSTEM_COLORS: [(u8, u8, u8); 16] = [
+    (0x00, 0x00, 0x00), (0xcd, 0x00, 0x00), (0x00, 0xcd, 0x00), (0xcd, 0xcd, 0x00),
+    (0x00, 0x00, 0xee), (0xcd, 0x00, 0xcd), (0x00, 0xcd, 0xcd), (0xe5, 0xe5, 0xe5),
+    (0x7f, 0x7f, 0x7f), (0xff, 0x00, 0x00), (0x00, 0xff, 0x00), (0xff, 0xff, 0x00),
+    (0x5c, 0x5c, 0xff), (0xff, 0x00, 0xff), (0x00, 0xff, 0xff), (0xff, 0xff, 0xff),
+];
+
+/// Channel levels of the 6x6x6 color cube in the 256 color palette.
+const CUBE_LEVELS: [u8; 6] = [0x00, 0x5f, 0x87, 0xaf, 0xd7, 0xff];
+
+/// Named colors in palette order, the only ones available in 16 color mode.
+const NAMED_COLORS: [Color; 8] = [
+    Color::Black, Color::Red, Color::Green, Color::Yellow,
+    Color::Blue, Color::Magenta, Color::Cyan, Color::White,
+];
+
+impl Color {
+    /// Palette index for 256 color mode. Rgb colors are mapped to the closest
+    /// palette entry, `Color::Default` to the terminal default. Bytes above 255
+    /// are clamped to 255.
+    pub fn as_256color(&self) -> u16 {
+        match *self {
+            Color::Default => PALETTE_DEFAULT,
+            Color::Black | Color::Byte(0) => PALETTE_BLACK,
+            Color::Red => 0x01,
+            Color::Green => 0x02,
+            Color::Yellow => 0x03,
+            Color::Blue => 0x04,
+            Color::Magenta => 0x05,
+            Color::Cyan => 0x06,
+            Color::White => 0x07,
+            Color::Byte(b) => b.min(0xff),
+            Color::Rgb(r, g, b) => nearest_256color(r, g, b),
+        }
+    }
+
+    /// Color code for 16 color mode. Byte and Rgb colors are mapped to the closest
+    /// named color.
+    pub fn as_16color(&self) -> u16 {
+        match *self {
+            Color::Default => PALETTE_DEFAULT,
+            Color::Black => PALETTE_BLACK,
+            Color::Red => 0x01,
+            Color::Green => 0x02,
+            Color::Yellow => 0x03,
+            Color::Blue => 0x04,
+            Color::Magenta => 0x05,
+            Color::Cyan => 0x06,
+            Color::White => 0x0F,
+            Color::Byte(_) | Color::Rgb(..) => self.as_named().as_16color(),
+        }
+    }
+
+    /// Red, green and blue components of the color, using the xterm palette for
+    /// named and byte colors. `Color::Default` has no known value and yields `None`.
+    pub fn as_rgb(&self) -> Option<(u8, u8, u8)> {
+        match *self {
+            Color::Black => Some(SYSTEM_COLORS[0]),
+            Color::Red => Some(SYSTEM_COLORS[1]),
+            Color::Green => Some(SYSTEM_COLORS[2]),
+            Color::Yellow => Some(SYSTEM_COLORS[3]),
+            Color::Blue => Some(SYSTEM_COLORS[4]),
+            Color::Magenta => Some(SYSTEM_COLORS[5]),
+            Color::Cyan => Some(SYSTEM_COLORS[6]),
+            Color::White => Some(SYSTEM_COLORS[7]),
+            Color::Byte(b) => Some(palette_rgb(b.min(0xff) as u8)),
+            Color::Rgb(r, g, b) => Some((r, g, b)),
+            Color::Default => None,
+        }
+    }
+
+    /// Packs the color as `0xRRGGBB` for true color output.
+    pub fn as_truecolor(&self) -> u32 {
+        match self.as_rgb() {
+            Some((r, g, b)) => (r as u32) << 16 | (g as u32) << 8 | b as u32,
+            None => TRUECOLOR_DEFAULT,
+        }
+    }
+
+    /// Decodes a foreground or background cell attribute written in `mode`. The
+    /// inverse of the `as_*color` methods, except that colors sharing a code come
+    /// back as the named one.
+    pub fn from_attribute(attr: u32, mode: OutputMode) -> Color {
+        match mode {
+            OutputMode::TrueColor => {
+                if attr & TRUECOLOR_DEFAULT != 0 {
+                    Color::Default
+                } else {
+                    Color::Rgb((attr >> 16) as u8, (attr >> 8) as u8, attr as u8)
+                }
+            },
+            OutputMode::EightBit => match (attr & 0xff) as u16 {
+                PALETTE_DEFAULT => Color::Default,
+                PALETTE_BLACK => Color::Black,
+                b @ 0x01..=0x07 => NAMED_COLORS[b as usize],
+                b => Color::Byte(b),
+            },
+            OutputMode::Normal | OutputMode::NoOutput => match (attr & 0x1f) as u16 {
+                PALETTE_DEFAULT => Color::Default,
+                PALETTE_BLACK => Color::Black,
+                0x0f => Color::White,
+                b @ 0x01..=0x07 => NAMED_COLORS[b as usize],
+                b => Color::Byte(b),
+            },
+        }
+    }
+
+    /// Closest of the eight named colors. Named colors and `Color::Default` are
+    /// returned unchanged.
+    pub fn as_named(&self) -> Color {
+        match *self {
+            Color::Byte(_) | Color::Rgb(..) => {
+                // `as_rgb` only returns `None` for `Color::Default`
+                let (r, g, b) = self.as_rgb().unwrap();
+                let target = Lab::from_rgb(r, g, b);
+                let mut nearest = NAMED_COLORS[0];
+                let mut nearest_distance = f64::MAX;
+                for (i, &(nr, ng, nb)) in SYSTEM_COLORS[..8].iter().enumerate() {
+                    let distance = target.distance(&Lab::from_rgb(nr, ng, nb));
+                    if distance < nearest_distance {
+                        nearest = NAMED_COLORS[i];
+                        nearest_distance = distance;
+                    }
+                }
+                nearest
+            },
+            color => color,
+        }
+    }
+
+    /// Converts the color to the closest one representable in `mode`.
+    ///
+    /// True color keeps everything, 256 color mode turns Rgb colors into palette
+    /// bytes and 16 color mode only keeps named colors.
+    pub fn downsample(&self, mode: OutputMode) -> Color {
+        match mode {
+            OutputMode::TrueColor => *self,
+            OutputMode::EightBit => match *self {
+                Color::Rgb(..) => Color::Byte(self.as_256color()),
+                color => color,
+            },
+            OutputMode::Normal | OutputMode::NoOutput => self.as_named(),
+        }
+    }
+}
+
+impl Default for Color {
+    fn default() -> Color {
+        Color::Black
+    }
+}
+
+/// Color of an entry in the xterm 256 color palette.
+fn palette_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => SYSTEM_COLORS[index as usize],
+        16..=231 => {
+            let i = (index - 16) as usize;
+            (CUBE_LEVELS[i / 36], CUBE_LEVELS[(i / 6) % 6], CUBE_LEVELS[i % 6])
+        },
+        _ => {
+            let level = 8 + 10 * (index - 232);
+            (level, level, level)
+        },
+    }
+}
+
+/// Closest entry of the 256 color palette by perceptual distance. The system
+/// colors are skipped since terminals commonly remap them.
+fn nearest_256color(r: u8, g: u8, b: u8) -> u16 {
+    let target = Lab::from_rgb(r, g, b);
+    let mut nearest = 16;
+    let mut nearest_distance = f64::MAX;
+    for index in 16..256u16 {
+        let (pr, pg, pb) = palette_rgb(index as u8);
+        let distance = target.distance(&Lab::from_rgb(pr, pg, pb));
+        if distance < nearest_distance {
+            nearest = index;
+            nearest_distance = distance;
+        }
+    }
+    nearest
+}
+
+/// A color in the CIE L*a*b* space, where euclidean distance approximates the
+/// perceived difference between colors.
+struct Lab {
+    l: f64,
+    a: f64,
+    b: f64,
+}
+
+impl Lab {
+    fn from_rgb(r: u8, g: u8, b: u8) -> Lab {
+        fn linear(channel: u8) -> f64 {
+            let c = channel as f64 / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        }
+
+        fn f(t: f64) -> f64 {
+            if t > 216.0 / 24389.0 {
+                t.cbrt()
+            } else {
+                (24389.0 / 27.0 * t + 16.0) / 116.0
+            }
+        }
+
+        let (r, g, b) = (linear(r), linear(g), linear(b));
+        // sRGB to XYZ, normalized to the D65 white point
+        let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+        let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+        let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+        Lab {
+            l: 116.0 * f(y) - 16.0,
+            a: 500.0 * (f(x) - f(y)),
+            b: 200.0 * (f(y) - f(z)),
+        }
+    }
+
+    fn distance(&self, other: &Lab) -> f64 {
+        let dl = self.l - other.l;
+        let da = self.a - other.a;
+        let db = self.b - other.b;
+        dl * dl + da * da + db * db
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{nearest_256color, palette_rgb, Color, Lab, NAMED_COLORS};
+    use backend::HeadlessBackend;
+    use std::default::Default;
+    use {OutputMode, RustBox, RB_NORMAL};
+
+    #[test]
+    fn named_colors_round_trip() {
+        for &mode in [OutputMode::Normal, OutputMode::EightBit].iter() {
+            let backend = HeadlessBackend::new(1, 1);
+            let screen = backend.screen();
+            let mut rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+            rb.set_output_mode(mode);
+            for &color in NAMED_COLORS.iter().chain(Some(Color::Default).iter()) {
+                rb.print(0, 0, RB_NORMAL, color, color, "x");
+                rb.present();
+                let cell = screen.cell(0, 0).unwrap();
+                assert_eq!((cell.fg, cell.bg), (color, color), "{:?} in {:?}", color, mode);
+            }
+        }
+    }
+
+    #[test]
+    fn palette_colors_map_to_themselves() {
+        for index in 16..256u16 {
+            let (r, g, b) = palette_rgb(index as u8);
+            assert_eq!(nearest_256color(r, g, b), index);
+        }
+    }
+
+    #[test]
+    fn nearest_256color_by_perceived_distance() {
+        // the system colors are never picked, black and white come from the cube
+        assert_eq!(nearest_256color(0, 0, 0), 16);
+        assert_eq!(nearest_256color(0xff, 0xff, 0xff), 231);
+        assert_eq!(nearest_256color(0xfe, 0x01, 0x02), 196);
+        // grays go to the gray ramp
+        assert_eq!(nearest_256color(0x80, 0x80, 0x80), 244);
+        assert_eq!(nearest_256color(0x0a, 0x0b, 0x09), 232);
+    }
+
+    #[test]
+    fn lab() {
+        let black = Lab::from_rgb(0, 0, 0);
+        let white = Lab::from_rgb(0xff, 0xff, 0xff);
+        assert!(black.l.abs() < 1e-6);
+        assert!((white.l - 100.0).abs() < 1e-3);
+        assert!(white.a.abs() < 0.5 && white.b.abs() < 0.5);
+        // dark blue is perceptually closer to black than dark green is
+        let blue = Lab::from_rgb(0, 0, 0x80);
+        let green = Lab::from_rgb(0, 0x80, 0);
+        assert!(black.distance(&blue) < black.distance(&green));
+    }
+
+    #[test]
+    fn downsampling() {
+        assert_eq!(Color::Rgb(0xff, 0, 0).downsample(OutputMode::EightBit), Color::Byte(196));
+        assert_eq!(Color::Rgb(0xf0, 0x10, 0x10).downsample(OutputMode::Normal), Color::Red);
+        assert_eq!(Color::Byte(21).downsample(OutputMode::Normal), Color::Blue);
+        assert_eq!(Color::Byte(21).downsample(OutputMode::EightBit), Color::Byte(21));
+        assert_eq!(Color::Default.downsample(OutputMode::Normal), Color::Default);
+        assert_eq!(Color::Rgb(1, 2, 3).downsample(OutputMode::TrueColor), Color::Rgb(1, 2, 3));
+        assert_eq!(Color::Byte(300).as_256color(), 0xff);
+        assert_eq!(Color::Byte(0).as_256color(), Color::Black.as_256color());
+    }
+}
@@ -1,13 +1,99 @@
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+use std::str;
+
+/// Key codes of termbox key events, which every backend reports keys with.
+pub mod codes {
+    pub const TB_KEY_F1: u16 = 0xffff;
+    pub const TB_KEY_F2: u16 = 0xffff - 1;
+    pub const TB_KEY_F3: u16 = 0xffff - 2;
+    pub const TB_KEY_F4: u16 = 0xffff - 3;
+    pub const TB_KEY_F5: u16 = 0xffff - 4;
+    pub const TB_KEY_F6: u16 = 0xffff - 5;
+    pub const TB_KEY_F7: u16 = 0xffff - 6;
+    pub const TB_KEY_F8: u16 = 0xffff - 7;
+    pub const TB_KEY_F9: u16 = 0xffff - 8;
+    pub const TB_KEY_F10: u16 = 0xffff - 9;
+    pub const TB_KEY_F11: u16 = 0xffff - 10;
+    pub const TB_KEY_F12: u16 = 0xffff - 11;
+    pub const TB_KEY_INSERT: u16 = 0xffff - 12;
+    pub const TB_KEY_DELETE: u16 = 0xffff - 13;
+    pub const TB_KEY_HOME: u16 = 0xffff - 14;
+    pub const TB_KEY_END: u16 = 0xffff - 15;
+    pub const TB_KEY_PGUP: u16 = 0xffff - 16;
+    pub const TB_KEY_PGDN: u16 = 0xffff - 17;
+    pub const TB_KEY_ARROW_UP: u16 = 0xffff - 18;
+    pub const TB_KEY_ARROW_DOWN: u16 = 0xffff - 19;
+    pub const TB_KEY_ARROW_LEFT: u16 = 0xffff - 20;
+    pub const TB_KEY_ARROW_RIGHT: u16 = 0xffff - 21;
+    pub const TB_KEY_MOUSE_LEFT: u16 = 0xffff - 22;
+    pub const TB_KEY_MOUSE_RIGHT: u16 = 0xffff - 23;
+    pub const TB_KEY_MOUSE_MIDDLE: u16 = 0xffff - 24;
+    pub const TB_KEY_MOUSE_RELEASE: u16 = 0xffff - 25;
+    pub const TB_KEY_MOUSE_WHEEL_UP: u16 = 0xffff - 26;
+    pub const TB_KEY_MOUSE_WHEEL_DOWN: u16 = 0xffff - 27;
+    /// Shift+Tab. Termbox has no code of its own for it, this is the next free one.
+    pub const TB_KEY_BACK_TAB: u16 = 0xffff - 28;
+
+    pub const TB_KEY_CTRL_TILDE: u16 = 0x00;
+    pub const TB_KEY_CTRL_2: u16 = 0x00;
+    pub const TB_KEY_CTRL_A: u16 = 0x01;
+    pub const TB_KEY_CTRL_B: u16 = 0x02;
+    pub const TB_KEY_CTRL_C: u16 = 0x03;
+    pub const TB_KEY_CTRL_D: u16 = 0x04;
+    pub const TB_KEY_CTRL_E: u16 = 0x05;
+    pub const TB_KEY_CTRL_F: u16 = 0x06;
+    pub const TB_KEY_CTRL_G: u16 = 0x07;
+    pub const TB_KEY_BACKSPACE: u16 = 0x08;
+    pub const TB_KEY_CTRL_H: u16 = 0x08;
+    pub const TB_KEY_TAB: u16 = 0x09;
+    pub const TB_KEY_CTRL_I: u16 = 0x09;
+    pub const TB_KEY_CTRL_J: u16 = 0x0a;
+    pub const TB_KEY_CTRL_K: u16 = 0x0b;
+    pub const TB_KEY_CTRL_L: u16 = 0x0c;
+    pub const TB_KEY_ENTER: u16 = 0x0d;
+    pub const TB_KEY_CTRL_M: u16 = 0x0d;
+    pub const TB_KEY_CTRL_N: u16 = 0x0e;
+    pub const TB_KEY_CTRL_O: u16 = 0x0f;
+    pub const TB_KEY_CTRL_P: u16 = 0x10;
+    pub const TB_KEY_CTRL_Q: u16 = 0x11;
+    pub const TB_KEY_CTRL_R: u16 = 0x12;
+    pub const TB_KEY_CTRL_S: u16 = 0x13;
+    pub const TB_KEY_CTRL_T: u16 = 0x14;
+    pub const TB_KEY_CTRL_U: u16 = 0x15;
+    pub const TB_KEY_CTRL_V: u16 = 0x16;
+    pub const TB_KEY_CTRL_W: u16 = 0x17;
+    pub const TB_KEY_CTRL_X: u16 = 0x18;
+    pub const TB_KEY_CTRL_Y: u16 = 0x19;
+    pub const TB_KEY_CTRL_Z: u16 = 0x1a;
+    pub const TB_KEY_ESC: u16 = 0x1b;
+    pub const TB_KEY_CTRL_LSQ_BRACKET: u16 = 0x1b;
+    pub const TB_KEY_CTRL_3: u16 = 0x1b;
+    pub const TB_KEY_CTRL_4: u16 = 0x1c;
+    pub const TB_KEY_CTRL_BACKSLASH: u16 = 0x1c;
+    pub const TB_KEY_CTRL_5: u16 = 0x1d;
+    pub const TB_KEY_CTRL_RSQ_BRACKET: u16 = 0x1d;
+    pub const TB_KEY_CTRL_6: u16 = 0x1e;
+    pub const TB_KEY_CTRL_7: u16 = 0x1f;
+    pub const TB_KEY_CTRL_SLASH: u16 = 0x1f;
+    pub const TB_KEY_CTRL_UNDERSCORE: u16 = 0x1f;
+    pub const TB_KEY_SPACE: u16 = 0x20;
+    pub const TB_KEY_BACKSPACE2: u16 = 0x7f;
+    pub const TB_KEY_CTRL_8: u16 = 0x7f;
+}
+
+/// Bits of the termbox `meta` field, termbox2's `TB_MOD_*`. `MOD_MOTION` is
+/// only set on mouse events.
+pub const MOD_ALT: u8 = 0x01;
+pub const MOD_CTRL: u8 = 0x02;
+pub const MOD_SHIFT: u8 = 0x04;
+pub const MOD_MOTION: u8 = 0x08;
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Hash, Debug)]
 pub struct Modifiers {
     pub ctrl: bool,
     pub shift: bool,
     pub alt: bool,
 }
 
-/**
- * Shift is incorrectly handled for the moment
- */
 impl Modifiers {
     pub fn new() -> Self {
         Self {
@@ -19,28 +105,77 @@ impl Modifiers {
 
     pub fn new_all(ctrl: bool, shift:bool, alt:bool) -> Self {
         Self {
-            ctrl: ctrl,
-            shift: shift,
-            alt: alt,
+            ctrl,
+            shift,
+            alt,
         }
     }
 
     pub fn shift_down(&self) -> bool {
-        return self.shift;
+        self.shift
     }
 
     pub fn ctrl_down(&self) -> bool {
-        return self.ctrl;
+        self.ctrl
     }
 
     pub fn alt_down(&self) -> bool {
-        return self.alt;
+        self.alt
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.ctrl && !self.shift && !self.alt
+    }
+
+    /// Decodes the `MOD_*` bits of the termbox `meta` field.
+    pub fn from_meta(meta: u8) -> Self {
+        Self {
+            ctrl: meta & MOD_CTRL != 0,
+            shift: meta & MOD_SHIFT != 0,
+            alt: meta & MOD_ALT != 0,
+        }
+    }
+
+    pub fn as_meta(&self) -> u8 {
+        let mut meta = 0;
+        if self.alt {
+            meta |= MOD_ALT;
+        }
+        if self.ctrl {
+            meta |= MOD_CTRL;
+        }
+        if self.shift {
+            meta |= MOD_SHIFT;
+        }
+        meta
+    }
+
+    /// Decodes the modifier parameter xterm appends to special keys, e.g. the
+    /// `5` in `ESC[1;5C` (Ctrl+Right). The parameter is one more than a bit mask
+    /// of shift (1), alt (2) and ctrl (4); meta (8) is reported as alt.
+    pub fn from_xterm_param(param: u32) -> Self {
+        let mask = param.saturating_sub(1);
+        Self {
+            ctrl: mask & 4 != 0,
+            shift: mask & 1 != 0,
+            alt: mask & (2 | 8) != 0,
+        }
+    }
+
+    pub fn union(&self, other: Modifiers) -> Self {
+        Self {
+            ctrl: self.ctrl || other.ctrl,
+            shift: self.shift || other.shift,
+            alt: self.alt || other.alt,
+        }
     }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Key {
     Tab,
+    /// Shift+Tab, reported by terminals as `ESC[Z`
+    BackTab,
     Enter,
     Esc,
     Backspace,
@@ -55,6 +190,8 @@ pub enum Key {
     End,
     PageUp,
     PageDown,
+    /// Center key of the keypad with num lock off
+    Begin,
 
     Char(char),
     F(u32),
@@ -77,75 +214,384 @@ impl ExtendedKey {
 
     pub fn new(key: Key, modifiers: Modifiers) -> Self {
         Self {
-            key: key,
-            modifiers: modifiers,
+            key,
+            modifiers,
             raw_emod: 0,
             raw_ch: 0,
             raw_key: 0,
         }
     }
 
+    /// The termbox key code of special keys, as reported in `raw_key`.
+    pub fn code(&self) -> u16 {
+        match self.key {
+            Key::Tab => codes::TB_KEY_TAB,
+            Key::BackTab => codes::TB_KEY_BACK_TAB,
+            Key::Enter => codes::TB_KEY_ENTER,
+            Key::Esc => codes::TB_KEY_ESC,
+            Key::Backspace => codes::TB_KEY_BACKSPACE2,
+            Key::Right => codes::TB_KEY_ARROW_RIGHT,
+            Key::Left => codes::TB_KEY_ARROW_LEFT,
+            Key::Up => codes::TB_KEY_ARROW_UP,
+            Key::Down => codes::TB_KEY_ARROW_DOWN,
+            Key::Delete => codes::TB_KEY_DELETE,
+            Key::Insert => codes::TB_KEY_INSERT,
+            Key::Home => codes::TB_KEY_HOME,
+            Key::End => codes::TB_KEY_END,
+            Key::PageUp => codes::TB_KEY_PGUP,
+            Key::PageDown => codes::TB_KEY_PGDN,
+            Key::F(n) if (1..=12).contains(&n) => codes::TB_KEY_F1 - (n as u16 - 1),
+            Key::Char(c) if self.modifiers.ctrl && c.is_ascii_lowercase() => c as u16 - 'a' as u16 + 1,
+            Key::Unknown(code) => code,
+            _ => 0,
+        }
+    }
+
+    /// Adds `modifiers` to the key, turning Shift+Tab into `Key::BackTab`.
+    pub fn with_modifiers(&self, modifiers: Modifiers) -> Self {
+        let mut key = *self;
+        key.modifiers = self.modifiers.union(modifiers);
+        if key.key == Key::Tab && key.modifiers.shift {
+            key.key = Key::BackTab;
+            key.modifiers.shift = false;
+        }
+        key
+    }
+
     pub fn from_code(code: u16) -> Option<ExtendedKey> {
         let empty_modifiers = Modifiers::new();
         let ctrl = Modifiers{ctrl:true, ..empty_modifiers};
         match code {
-            termbox::TB_KEY_CTRL_A => Some(ExtendedKey::new(Key::Char('a'), ctrl)),
-            termbox::TB_KEY_CTRL_B => Some(ExtendedKey::new(Key::Char('b'), ctrl)),
-            termbox::TB_KEY_CTRL_C => Some(ExtendedKey::new(Key::Char('c'), ctrl)),
-            termbox::TB_KEY_CTRL_D => Some(ExtendedKey::new(Key::Char('d'), ctrl)),
-            termbox::TB_KEY_CTRL_E => Some(ExtendedKey::new(Key::Char('e'), ctrl)),
-            termbox::TB_KEY_CTRL_F => Some(ExtendedKey::new(Key::Char('f'), ctrl)),
-            termbox::TB_KEY_BACKSPACE => Some(ExtendedKey::new(Key::Backspace, empty_modifiers)),
-            termbox::TB_KEY_TAB => Some(ExtendedKey::new(Key::Tab, empty_modifiers)),
-            termbox::TB_KEY_CTRL_J => Some(ExtendedKey::new(Key::Char('j'), ctrl)),
-            termbox::TB_KEY_CTRL_K => Some(ExtendedKey::new(Key::Char('k'), ctrl)),
-            termbox::TB_KEY_CTRL_L => Some(ExtendedKey::new(Key::Char('l'), ctrl)),
-            termbox::TB_KEY_ENTER => Some(ExtendedKey::new(Key::Enter, empty_modifiers)),
-            termbox::TB_KEY_CTRL_N => Some(ExtendedKey::new(Key::Char('n'), ctrl)),
-            termbox::TB_KEY_CTRL_O => Some(ExtendedKey::new(Key::Char('o'), ctrl)),
-            termbox::TB_KEY_CTRL_P => Some(ExtendedKey::new(Key::Char('p'), ctrl)),
-            termbox::TB_KEY_CTRL_Q => Some(ExtendedKey::new(Key::Char('q'), ctrl)),
-            termbox::TB_KEY_CTRL_R => Some(ExtendedKey::new(Key::Char('r'), ctrl)),
-            termbox::TB_KEY_CTRL_S => Some(ExtendedKey::new(Key::Char('s'), ctrl)),
-            termbox::TB_KEY_CTRL_T => Some(ExtendedKey::new(Key::Char('t'), ctrl)),
-            termbox::TB_KEY_CTRL_U => Some(ExtendedKey::new(Key::Char('u'), ctrl)),
-            termbox::TB_KEY_CTRL_V => Some(ExtendedKey::new(Key::Char('v'), ctrl)),
-            termbox::TB_KEY_CTRL_W => Some(ExtendedKey::new(Key::Char('w'), ctrl)),
-            termbox::TB_KEY_CTRL_X => Some(ExtendedKey::new(Key::Char('x'), ctrl)),
-            termbox::TB_KEY_CTRL_Y => Some(ExtendedKey::new(Key::Char('y'), ctrl)),
-            termbox::TB_KEY_CTRL_Z => Some(ExtendedKey::new(Key::Char('z'), ctrl)),
-            termbox::TB_KEY_ESC => Some(ExtendedKey::new(Key::Esc, empty_modifiers)),
-            termbox::TB_KEY_CTRL_BACKSLASH => Some(ExtendedKey::new(Key::Char('\\'), ctrl)),
-            termbox::TB_KEY_CTRL_RSQ_BRACKET => Some(ExtendedKey::new(Key::Char(']'), ctrl)),
-            termbox::TB_KEY_CTRL_6 => Some(ExtendedKey::new(Key::Char('6'), ctrl)),
-            termbox::TB_KEY_CTRL_7 => Some(ExtendedKey::new(Key::Char('7'), ctrl)),
-            termbox::TB_KEY_CTRL_SLASH => Some(ExtendedKey::new(Key::Char('/'), ctrl)),
-            termbox::TB_KEY_CTRL_UNDERSCORE => Some(ExtendedKey::new(Key::Char('_'), ctrl)),
-            termbox::TB_KEY_SPACE => Some(ExtendedKey::new(Key::Char(' '), empty_modifiers)),
-            termbox::TB_KEY_BACKSPACE => Some(ExtendedKey::new(Key::Backspace, empty_modifiers)),
-            termbox::TB_KEY_ARROW_RIGHT => Some(ExtendedKey::new(Key::Right, empty_modifiers)),
-            termbox::TB_KEY_ARROW_LEFT => Some(ExtendedKey::new(Key::Left, empty_modifiers)),
-            termbox::TB_KEY_ARROW_DOWN => Some(ExtendedKey::new(Key::Down, empty_modifiers)),
-            termbox::TB_KEY_ARROW_UP => Some(ExtendedKey::new(Key::Up, empty_modifiers)),
-            termbox::TB_KEY_F1 => Some(ExtendedKey::new(Key::F(1), empty_modifiers)),
-            termbox::TB_KEY_F2 => Some(ExtendedKey::new(Key::F(2), empty_modifiers)),
-            termbox::TB_KEY_F3 => Some(ExtendedKey::new(Key::F(3), empty_modifiers)),
-            termbox::TB_KEY_F4 => Some(ExtendedKey::new(Key::F(4), empty_modifiers)),
-            termbox::TB_KEY_F5 => Some(ExtendedKey::new(Key::F(5), empty_modifiers)),
-            termbox::TB_KEY_F6 => Some(ExtendedKey::new(Key::F(6), empty_modifiers)),
-            termbox::TB_KEY_F7 => Some(ExtendedKey::new(Key::F(7), empty_modifiers)),
-            termbox::TB_KEY_F8 => Some(ExtendedKey::new(Key::F(8), empty_modifiers)),
-            termbox::TB_KEY_F9 => Some(ExtendedKey::new(Key::F(9), empty_modifiers)),
-            termbox::TB_KEY_F10 => Some(ExtendedKey::new(Key::F(10), empty_modifiers)),
-            termbox::TB_KEY_F11 => Some(ExtendedKey::new(Key::F(11), empty_modifiers)),
-            termbox::TB_KEY_F12 => Some(ExtendedKey::new(Key::F(12), empty_modifiers)),
-            termbox::TB_KEY_INSERT => Some(ExtendedKey::new(Key::Insert, empty_modifiers)),
-            termbox::TB_KEY_DELETE => Some(ExtendedKey::new(Key::Delete, empty_modifiers)),
-            termbox::TB_KEY_HOME => Some(ExtendedKey::new(Key::Home, empty_modifiers)),
-            termbox::TB_KEY_END => Some(ExtendedKey::new(Key::End, empty_modifiers)),
-            termbox::TB_KEY_PGUP => Some(ExtendedKey::new(Key::PageUp, empty_modifiers)),
-            termbox::TB_KEY_PGDN => Some(ExtendedKey::new(Key::PageDown, empty_modifiers)),
+            codes::TB_KEY_CTRL_A => Some(ExtendedKey::new(Key::Char('a'), ctrl)),
+            codes::TB_KEY_CTRL_B => Some(ExtendedKey::new(Key::Char('b'), ctrl)),
+            codes::TB_KEY_CTRL_C => Some(ExtendedKey::new(Key::Char('c'), ctrl)),
+            codes::TB_KEY_CTRL_D => Some(ExtendedKey::new(Key::Char('d'), ctrl)),
+            codes::TB_KEY_CTRL_E => Some(ExtendedKey::new(Key::Char('e'), ctrl)),
+            codes::TB_KEY_CTRL_F => Some(ExtendedKey::new(Key::Char('f'), ctrl)),
+            codes::TB_KEY_BACKSPACE => Some(ExtendedKey::new(Key::Backspace, empty_modifiers)),
+            codes::TB_KEY_TAB => Some(ExtendedKey::new(Key::Tab, empty_modifiers)),
+            codes::TB_KEY_BACK_TAB => Some(ExtendedKey::new(Key::BackTab, empty_modifiers)),
+            codes::TB_KEY_CTRL_J => Some(ExtendedKey::new(Key::Char('j'), ctrl)),
+            codes::TB_KEY_CTRL_K => Some(ExtendedKey::new(Key::Char('k'), ctrl)),
+            codes::TB_KEY_CTRL_L => Some(ExtendedKey::new(Key::Char('l'), ctrl)),
+            codes::TB_KEY_ENTER => Some(ExtendedKey::new(Key::Enter, empty_modifiers)),
+            codes::TB_KEY_CTRL_N => Some(ExtendedKey::new(Key::Char('n'), ctrl)),
+            codes::TB_KEY_CTRL_O => Some(ExtendedKey::new(Key::Char('o'), ctrl)),
+            codes::TB_KEY_CTRL_P => Some(ExtendedKey::new(Key::Char('p'), ctrl)),
+            codes::TB_KEY_CTRL_Q => Some(ExtendedKey::new(Key::Char('q'), ctrl)),
+            codes::TB_KEY_CTRL_R => Some(ExtendedKey::new(Key::Char('r'), ctrl)),
+            codes::TB_KEY_CTRL_S => Some(ExtendedKey::new(Key::Char('s'), ctrl)),
+            codes::TB_KEY_CTRL_T => Some(ExtendedKey::new(Key::Char('t'), ctrl)),
+            codes::TB_KEY_CTRL_U => Some(ExtendedKey::new(Key::Char('u'), ctrl)),
+            codes::TB_KEY_CTRL_V => Some(ExtendedKey::new(Key::Char('v'), ctrl)),
+            codes::TB_KEY_CTRL_W => Some(ExtendedKey::new(Key::Char('w'), ctrl)),
+            codes::TB_KEY_CTRL_X => Some(ExtendedKey::new(Key::Char('x'), ctrl)),
+            codes::TB_KEY_CTRL_Y => Some(ExtendedKey::new(Key::Char('y'), ctrl)),
+            codes::TB_KEY_CTRL_Z => Some(ExtendedKey::new(Key::Char('z'), ctrl)),
+            codes::TB_KEY_ESC => Some(ExtendedKey::new(Key::Esc, empty_modifiers)),
+            codes::TB_KEY_CTRL_BACKSLASH => Some(ExtendedKey::new(Key::Char('\\'), ctrl)),
+            codes::TB_KEY_CTRL_RSQ_BRACKET => Some(ExtendedKey::new(Key::Char(']'), ctrl)),
+            codes::TB_KEY_CTRL_6 => Some(ExtendedKey::new(Key::Char('6'), ctrl)),
+            codes::TB_KEY_CTRL_7 => Some(ExtendedKey::new(Key::Char('7'), ctrl)),
+            codes::TB_KEY_SPACE => Some(ExtendedKey::new(Key::Char(' '), empty_modifiers)),
+            codes::TB_KEY_BACKSPACE2 => Some(ExtendedKey::new(Key::Backspace, empty_modifiers)),
+            codes::TB_KEY_ARROW_RIGHT => Some(ExtendedKey::new(Key::Right, empty_modifiers)),
+            codes::TB_KEY_ARROW_LEFT => Some(ExtendedKey::new(Key::Left, empty_modifiers)),
+            codes::TB_KEY_ARROW_DOWN => Some(ExtendedKey::new(Key::Down, empty_modifiers)),
+            codes::TB_KEY_ARROW_UP => Some(ExtendedKey::new(Key::Up, empty_modifiers)),
+            codes::TB_KEY_F1 => Some(ExtendedKey::new(Key::F(1), empty_modifiers)),
+            codes::TB_KEY_F2 => Some(ExtendedKey::new(Key::F(2), empty_modifiers)),
+            codes::TB_KEY_F3 => Some(ExtendedKey::new(Key::F(3), empty_modifiers)),
+            codes::TB_KEY_F4 => Some(ExtendedKey::new(Key::F(4), empty_modifiers)),
+            codes::TB_KEY_F5 => Some(ExtendedKey::new(Key::F(5), empty_modifiers)),
+            codes::TB_KEY_F6 => Some(ExtendedKey::new(Key::F(6), empty_modifiers)),
+            codes::TB_KEY_F7 => Some(ExtendedKey::new(Key::F(7), empty_modifiers)),
+            codes::TB_KEY_F8 => Some(ExtendedKey::new(Key::F(8), empty_modifiers)),
+            codes::TB_KEY_F9 => Some(ExtendedKey::new(Key::F(9), empty_modifiers)),
+            codes::TB_KEY_F10 => Some(ExtendedKey::new(Key::F(10), empty_modifiers)),
+            codes::TB_KEY_F11 => Some(ExtendedKey::new(Key::F(11), empty_modifiers)),
+            codes::TB_KEY_F12 => Some(ExtendedKey::new(Key::F(12), empty_modifiers)),
+            codes::TB_KEY_INSERT => Some(ExtendedKey::new(Key::Insert, empty_modifiers)),
+            codes::TB_KEY_DELETE => Some(ExtendedKey::new(Key::Delete, empty_modifiers)),
+            codes::TB_KEY_HOME => Some(ExtendedKey::new(Key::Home, empty_modifiers)),
+            codes::TB_KEY_END => Some(ExtendedKey::new(Key::End, empty_modifiers)),
+            codes::TB_KEY_PGUP => Some(ExtendedKey::new(Key::PageUp, empty_modifiers)),
+            codes::TB_KEY_PGDN => Some(ExtendedKey::new(Key::PageDown, empty_modifiers)),
             _     => None,
         }
     }
 }
+
+/// Parses a single key press from the start of `buf`, as sent by an xterm
+/// compatible terminal.
+///
+/// Returns the key and the number of bytes it took, or `None` when `buf` ends in
+/// the middle of a key and more input is needed. A lone ESC is only reported as
+/// `Key::Esc` when it is all there is in `buf`. Unrecognized escape sequences are
+/// consumed and reported as `Key::Unknown(0)`, mouse reports included.
+///
+/// This is how the native backend reads keys; termbox does its own parsing.
+pub fn parse_key(buf: &[u8]) -> Option<(ExtendedKey, usize)> {
+    if buf.is_empty() {
+        return None;
+    }
+    if buf[0] != 0x1b {
+        return parse_plain_key(buf);
+    }
+    match buf.get(1) {
+        None => Some((ExtendedKey::new(Key::Esc, Modifiers::new()), 1)),
+        Some(&b'[') => parse_csi(buf),
+        Some(&b'O') => parse_ss3(buf),
+        // ESC ESC on its own is Alt+Esc, otherwise ESC starts the next sequence
+        Some(&0x1b) if buf.len() > 2 && (buf[2] == b'[' || buf[2] == b'O') => {
+            parse_key(&buf[1..]).map(|(key, len)| (key.with_modifiers(alt()), len + 1))
+        },
+        Some(_) => {
+            parse_plain_key(&buf[1..]).map(|(key, len)| (key.with_modifiers(alt()), len + 1))
+        },
+    }
+}
+
+fn alt() -> Modifiers {
+    Modifiers::new_all(false, false, true)
+}
+
+/// Parses a key that isn't an escape sequence: control codes and UTF-8 characters.
+fn parse_plain_key(buf: &[u8]) -> Option<(ExtendedKey, usize)> {
+    let first = buf[0];
+    let key = match first {
+        0x00 => ExtendedKey::new(Key::Char(' '), Modifiers::new_all(true, false, false)),
+        0x09 => ExtendedKey::new(Key::Tab, Modifiers::new()),
+        0x0d | 0x0a => ExtendedKey::new(Key::Enter, Modifiers::new()),
+        0x08 | 0x7f => ExtendedKey::new(Key::Backspace, Modifiers::new()),
+        0x1b => ExtendedKey::new(Key::Esc, Modifiers::new()),
+        0x01..=0x1a => {
+            let c = (b'a' + first - 1) as char;
+            ExtendedKey::new(Key::Char(c), Modifiers::new_all(true, false, false))
+        },
+        0x1c => ExtendedKey::new(Key::Char('\\'), Modifiers::new_all(true, false, false)),
+        0x1d => ExtendedKey::new(Key::Char(']'), Modifiers::new_all(true, false, false)),
+        0x1e => ExtendedKey::new(Key::Char('6'), Modifiers::new_all(true, false, false)),
+        0x1f => ExtendedKey::new(Key::Char('/'), Modifiers::new_all(true, false, false)),
+        _ => {
+            let len = match first {
+                0xc0..=0xdf => 2,
+                0xe0..=0xef => 3,
+                0xf0..=0xf7 => 4,
+                _ => 1,
+            };
+            if buf.len() < len {
+                return None;
+            }
+            let c = str::from_utf8(&buf[..len]).ok().and_then(|s| s.chars().next());
+            let key = match c {
+                Some(c) => char_key(c),
+                None => ExtendedKey::new(Key::Unknown(first as u16), Modifiers::new()),
+            };
+            return Some((key, len));
+        },
+    };
+    let mut key = key;
+    key.raw_ch = first as u32;
+    key.raw_emod = key.modifiers.as_meta();
+    Some((key, 1))
+}
+
+/// A printable character. Shift is part of the character, so it isn't reported.
+fn char_key(c: char) -> ExtendedKey {
+    let mut key = ExtendedKey::new(Key::Char(c), Modifiers::new());
+    key.raw_ch = c as u32;
+    key
+}
+
+/// Parses `ESC [ params final`.
+fn parse_csi(buf: &[u8]) -> Option<(ExtendedKey, usize)> {
+    // Mouse reports are not keys, skip them whole: `ESC[<b;x;yM` or `m`, and
+    // `ESC[M` followed by three bytes
+    let unknown = ExtendedKey::new(Key::Unknown(0), Modifiers::new());
+    match buf.get(2) {
+        Some(&b'<') => return buf[3..].iter().position(|&b| b == b'M' || b == b'm').map(|i| (unknown, i + 4)),
+        Some(&b'M') => return if buf.len() >= 6 { Some((unknown, 6)) } else { None },
+        _ => (),
+    }
+    let end = match buf[2..].iter().position(|&b| (0x40..=0x7e).contains(&b)) {
+        Some(i) => i + 2,
+        None => return None,
+    };
+    let len = end + 1;
+    let params: Vec<u32> = match str::from_utf8(&buf[2..end]) {
+        Ok(s) => s.split(';').map(|p| p.parse().unwrap_or(0)).collect(),
+        Err(_) => vec![],
+    };
+    let param = |i: usize| params.get(i).cloned().unwrap_or(0);
+    let modifiers = Modifiers::from_xterm_param(param(1));
+
+    let key = match buf[end] {
+        b'A' => Key::Up,
+        b'B' => Key::Down,
+        b'C' => Key::Right,
+        b'D' => Key::Left,
+        b'E' => Key::Begin,
+        b'F' => Key::End,
+        b'H' => Key::Home,
+        b'P' => Key::F(1),
+        b'Q' => Key::F(2),
+        b'R' => Key::F(3),
+        b'S' => Key::F(4),
+        b'Z' => Key::BackTab,
+        b'~' => match param(0) {
+            // xterm modifyOtherKeys: ESC[27;modifier;code~
+            27 => return Some((code_key(param(2), modifiers), len)),
+            1 | 7 => Key::Home,
+            2 => Key::Insert,
+            3 => Key::Delete,
+            4 | 8 => Key::End,
+            5 => Key::PageUp,
+            6 => Key::PageDown,
+            n @ 11..=15 => Key::F(n - 10),
+            n @ 17..=21 => Key::F(n - 11),
+            n @ 23..=26 => Key::F(n - 12),
+            n @ 28..=29 => Key::F(n - 13),
+            n @ 31..=34 => Key::F(n - 14),
+            _ => Key::Unknown(0),
+        },
+        // fixterms / kitty: ESC[code;modifier u
+        b'u' => return Some((code_key(param(0), modifiers), len)),
+        _ => Key::Unknown(0),
+    };
+    let mut key = ExtendedKey::new(key, modifiers);
+    key.raw_emod = modifiers.as_meta();
+    Some((key, len))
+}
+
+/// Parses `ESC O final`, which some terminals send for arrows, Home/End and F1-F4.
+fn parse_ss3(buf: &[u8]) -> Option<(ExtendedKey, usize)> {
+    let mut end = 2;
+    while end < buf.len() && buf[end].is_ascii_digit() {
+        end += 1;
+    }
+    if end >= buf.len() {
+        return None;
+    }
+    let modifiers = str::from_utf8(&buf[2..end]).ok()
+        .and_then(|p| p.parse().ok())
+        .map(Modifiers::from_xterm_param)
+        .unwrap_or_default();
+    let key = match buf[end] {
+        b'A' => Key::Up,
+        b'B' => Key::Down,
+        b'C' => Key::Right,
+        b'D' => Key::Left,
+        b'E' => Key::Begin,
+        b'F' => Key::End,
+        b'H' => Key::Home,
+        b'M' => Key::Enter,
+        b'P' => Key::F(1),
+        b'Q' => Key::F(2),
+        b'R' => Key::F(3),
+        b'S' => Key::F(4),
+        _ => Key::Unknown(0),
+    };
+    let mut key = ExtendedKey::new(key, modifiers);
+    key.raw_emod = modifiers.as_meta();
+    Some((key, end + 1))
+}
+
+/// A key given by its character code together with xterm modifiers.
+fn code_key(code: u32, modifiers: Modifiers) -> ExtendedKey {
+    let key = match code {
+        0x09 => ExtendedKey::new(Key::Tab, Modifiers::new()),
+        0x0d => ExtendedKey::new(Key::Enter, Modifiers::new()),
+        0x1b => ExtendedKey::new(Key::Esc, Modifiers::new()),
+        0x08 | 0x7f => ExtendedKey::new(Key::Backspace, Modifiers::new()),
+        code => match ::std::char::from_u32(code) {
+            Some(c) => ExtendedKey::new(Key::Char(c), Modifiers::new()),
+            None => ExtendedKey::new(Key::Unknown(0), Modifiers::new()),
+        },
+    };
+    let mut key = key.with_modifiers(modifiers);
+    key.raw_ch = code;
+    key.raw_emod = key.modifiers.as_meta();
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_key, ExtendedKey, Key, Modifiers};
+
+    fn key(buf: &[u8]) -> Option<(Key, Modifiers, usize)> {
+        parse_key(buf).map(|(key, len)| (key.key, key.modifiers, len))
+    }
+
+    fn mods(ctrl: bool, shift: bool, alt: bool) -> Modifiers {
+        Modifiers::new_all(ctrl, shift, alt)
+    }
+
+    #[test]
+    fn plain_keys() {
+        let none = Modifiers::new();
+        assert_eq!(key(b"a"), Some((Key::Char('a'), none, 1)));
+        assert_eq!(key(b"A"), Some((Key::Char('A'), none, 1)));
+        assert_eq!(key(b"\x01"), Some((Key::Char('a'), mods(true, false, false), 1)));
+        assert_eq!(key(b"\r"), Some((Key::Enter, none, 1)));
+        assert_eq!(key(b"\x7f"), Some((Key::Backspace, none, 1)));
+        assert_eq!(key("é".as_bytes()), Some((Key::Char('é'), none, 2)));
+        // half of a UTF-8 character
+        assert_eq!(key(&"é".as_bytes()[..1]), None);
+    }
+
+    #[test]
+    fn escape_and_alt() {
+        assert_eq!(key(b"\x1b"), Some((Key::Esc, Modifiers::new(), 1)));
+        assert_eq!(key(b"\x1bx"), Some((Key::Char('x'), mods(false, false, true), 2)));
+        assert_eq!(key(b"\x1b\x1b[A"), Some((Key::Up, mods(false, false, true), 4)));
+    }
+
+    #[test]
+    fn csi_sequences() {
+        let none = Modifiers::new();
+        assert_eq!(key(b"\x1b[A"), Some((Key::Up, none, 3)));
+        assert_eq!(key(b"\x1b[1;5C"), Some((Key::Right, mods(true, false, false), 6)));
+        assert_eq!(key(b"\x1b[1;4D"), Some((Key::Left, mods(false, true, true), 6)));
+        assert_eq!(key(b"\x1b[3~"), Some((Key::Delete, none, 4)));
+        assert_eq!(key(b"\x1b[5;2~"), Some((Key::PageUp, mods(false, true, false), 6)));
+        assert_eq!(key(b"\x1b[15~"), Some((Key::F(5), none, 5)));
+        assert_eq!(key(b"\x1b[24~"), Some((Key::F(12), none, 5)));
+        assert_eq!(key(b"\x1b[Z"), Some((Key::BackTab, none, 3)));
+        assert_eq!(key(b"\x1b[27;5;9~"), Some((Key::Tab, mods(true, false, false), 9)));
+        assert_eq!(key(b"\x1b[97;3u"), Some((Key::Char('a'), mods(false, false, true), 7)));
+        assert_eq!(key(b"\x1b[9;2u"), Some((Key::BackTab, none, 6)));
+        assert_eq!(key(b"\x1b[99~x"), Some((Key::Unknown(0), none, 5)));
+        // incomplete
+        assert_eq!(key(b"\x1b[1;5"), None);
+    }
+
+    #[test]
+    fn mouse_reports_are_skipped_whole() {
+        let none = Modifiers::new();
+        assert_eq!(key(b"\x1b[<0;12;5Mx"), Some((Key::Unknown(0), none, 10)));
+        assert_eq!(key(b"\x1b[<0;12;5m"), Some((Key::Unknown(0), none, 10)));
+        assert_eq!(key(b"\x1b[M !!x"), Some((Key::Unknown(0), none, 6)));
+        assert_eq!(key(b"\x1b[<0;12"), None);
+        assert_eq!(key(b"\x1b[M !"), None);
+    }
+
+    #[test]
+    fn ss3_sequences() {
+        let none = Modifiers::new();
+        assert_eq!(key(b"\x1bOA"), Some((Key::Up, none, 3)));
+        assert_eq!(key(b"\x1bOP"), Some((Key::F(1), none, 3)));
+        assert_eq!(key(b"\x1bO5S"), Some((Key::F(4), mods(true, false, false), 4)));
+        assert_eq!(key(b"\x1bOM"), Some((Key::Enter, none, 3)));
+        assert_eq!(key(b"\x1bO"), None);
+    }
+
+    #[test]
+    fn codes_round_trip() {
+        for &k in [Key::Tab, Key::BackTab, Key::Enter, Key::Esc, Key::Up, Key::Delete, Key::F(7)].iter() {
+            let code = ExtendedKey::new(k, Modifiers::new()).code();
+            assert_eq!(ExtendedKey::from_code(code).map(|key| key.key), Some(k));
+        }
+        assert_eq!(Modifiers::from_meta(super::MOD_MOTION), Modifiers::new());
+    }
+}
@@ -0,0 +1,232 @@
+//! Splitting rectangles into rows or columns by constraints.
+
+use super::{Event, Rect};
+
+/// Which way a `Layout` lays out its areas.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Direction {
+    /// Side by side, as columns.
+    Horizontal,
+    /// Stacked on top of each other, as rows.
+    Vertical,
+}
+
+/// The size of one area of a `Layout`, along its direction.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Constraint {
+    /// Exactly this many cells.
+    Fixed(usize),
+    /// A percentage of the whole length.
+    Percentage(u16),
+    /// The fraction numerator / denominator of the whole length.
+    Ratio(u32, u32),
+    /// At least this many cells, growing into space no `Fill` takes.
+    Min(usize),
+    /// As many cells as are left, up to this many.
+    Max(usize),
+    /// A share of the space left over, in proportion to the weights of all
+    /// `Fill` areas.
+    Fill(u16),
+}
+
+/// Splits a rectangle into areas sized by constraints.
+///
+/// Space is handed out in order of priority: first `Fixed`, `Percentage`,
+/// `Ratio` and `Min` get their size, then `Max` areas grow up to their limit and
+/// what is left goes to the `Fill` areas, or to the `Min` ones if there are
+/// none. When there isn't enough room, the areas at the end are cut short.
+///
+/// ```
+/// use rustbox::Rect;
+/// use rustbox::layout::{Constraint, Layout};
+///
+/// let layout = Layout::vertical(vec![Constraint::Fixed(1), Constraint::Fill(1), Constraint::Fixed(1)]);
+/// let areas = layout.split(Rect::new(0, 0, 80, 24));
+/// assert_eq!(areas[1], Rect::new(0, 1, 80, 22));
+/// assert_eq!(areas[2], Rect::new(0, 23, 80, 1));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Layout {
+    direction: Direction,
+    constraints: Vec<Constraint>,
+    // The last split of the whole screen, see `update`
+    areas: Vec<Rect>,
+}
+
+impl Layout {
+    pub fn new(direction: Direction, constraints: Vec<Constraint>) -> Layout {
+        Layout {
+            direction,
+            constraints,
+            areas: vec![],
+        }
+    }
+
+    /// Areas next to each other, from left to right.
+    pub fn horizontal(constraints: Vec<Constraint>) -> Layout {
+        Layout::new(Direction::Horizontal, constraints)
+    }
+
+    /// Areas on top of each other, from top to bottom.
+    pub fn vertical(constraints: Vec<Constraint>) -> Layout {
+        Layout::new(Direction::Vertical, constraints)
+    }
+
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    /// One area for each constraint, covering `area` along the layout's direction.
+    pub fn split(&self, area: Rect) -> Vec<Rect> {
+        let total = match self.direction {
+            Direction::Horizontal => area.width,
+            Direction::Vertical => area.height,
+        };
+        let mut position = 0;
+        self.lengths(total).into_iter().map(|length| {
+            let rect = match self.direction {
+                Direction::Horizontal => Rect::new(area.x + position, area.y, length, area.height),
+                Direction::Vertical => Rect::new(area.x, area.y + position, area.width, length),
+            };
+            position += length;
+            rect
+        }).collect()
+    }
+
+    /// Splits the screen of `width` by `height` cells, keeping the result for
+    /// `areas`.
+    pub fn resize(&mut self, width: usize, height: usize) -> &[Rect] {
+        self.areas = self.split(Rect::new(0, 0, width, height));
+        &self.areas
+    }
+
+    /// Splits the screen again when `event` is a resize. Returns whether the
+    /// areas changed.
+    pub fn update(&mut self, event: &Event) -> bool {
+        match *event {
+            Event::ResizeEvent(w, h) => {
+                let old = self.areas.clone();
+                self.resize(w.max(0) as usize, h.max(0) as usize) != &old[..]
+            },
+            _ => false,
+        }
+    }
+
+    /// The areas of the screen from the last `resize` or `update`.
+    pub fn areas(&self) -> &[Rect] {
+        &self.areas
+    }
+
+    /// Lengths of the areas when splitting `total` cells.
+    fn lengths(&self, total: usize) -> Vec<usize> {
+        let mut lengths: Vec<usize> = self.constraints.iter().map(|c| match *c {
+            Constraint::Fixed(n) | Constraint::Min(n) => n,
+            Constraint::Percentage(p) => total * p as usize / 100,
+            Constraint::Ratio(_, 0) => 0,
+            Constraint::Ratio(n, d) => total * n as usize / d as usize,
+            Constraint::Max(_) | Constraint::Fill(_) => 0,
+        }).collect();
+
+        let mut left = total.saturating_sub(lengths.iter().sum());
+        for (length, c) in lengths.iter_mut().zip(self.constraints.iter()) {
+            if let Constraint::Max(n) = *c {
+                *length = n.min(left);
+                left -= *length;
+            }
+        }
+
+        let fill: Vec<usize> = self.constraints.iter().map(|c| match *c {
+            Constraint::Fill(weight) => weight as usize,
+            _ => 0,
+        }).collect();
+        let weights = if fill.iter().any(|&w| w > 0) {
+            fill
+        } else {
+            self.constraints.iter().map(|c| match *c {
+                Constraint::Min(_) => 1,
+                _ => 0,
+            }).collect()
+        };
+        distribute(&mut lengths, &weights, left);
+
+        // Cut short what doesn't fit.
+        let mut left = total;
+        for length in lengths.iter_mut() {
+            *length = (*length).min(left);
+            left -= *length;
+        }
+        lengths
+    }
+}
+
+/// Adds `amount` to `lengths` in proportion to `weights`, the remainder of the
+/// division going to the first ones.
+fn distribute(lengths: &mut [usize], weights: &[usize], amount: usize) {
+    let sum: usize = weights.iter().sum();
+    if sum == 0 {
+        return;
+    }
+    let mut given = 0;
+    for (length, &weight) in lengths.iter_mut().zip(weights) {
+        let share = amount * weight / sum;
+        *length += share;
+        given += share;
+    }
+    for (length, _) in lengths.iter_mut().zip(weights).filter(|&(_, &w)| w > 0) {
+        if given == amount {
+            break;
+        }
+        *length += 1;
+        given += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Constraint, Layout};
+    use Rect;
+
+    fn widths(constraints: Vec<Constraint>, total: usize) -> Vec<usize> {
+        Layout::horizontal(constraints).split(Rect::new(0, 0, total, 1)).iter().map(|r| r.width).collect()
+    }
+
+    #[test]
+    fn fills_share_what_is_left() {
+        use super::Constraint::*;
+        assert_eq!(widths(vec![Fixed(2), Fill(1), Fill(2)], 11), vec![2, 3, 6]);
+        // the remainder goes to the first fills
+        assert_eq!(widths(vec![Fill(1), Fill(1), Fill(1)], 10), vec![4, 3, 3]);
+        assert_eq!(widths(vec![Percentage(50), Ratio(1, 4), Fill(1)], 20), vec![10, 5, 5]);
+    }
+
+    #[test]
+    fn undersubscribed() {
+        use super::Constraint::*;
+        // no fills, the min areas grow instead
+        assert_eq!(widths(vec![Fixed(2), Min(1), Min(3)], 10), vec![2, 3, 5]);
+        // max areas stop at their limit and nothing takes the rest
+        assert_eq!(widths(vec![Max(3), Fixed(2)], 10), vec![3, 2]);
+        assert_eq!(widths(vec![Ratio(1, 0), Fixed(1)], 10), vec![0, 1]);
+    }
+
+    #[test]
+    fn oversubscribed() {
+        use super::Constraint::*;
+        // the areas at the end are cut short
+        assert_eq!(widths(vec![Fixed(6), Min(3), Fixed(4)], 8), vec![6, 2, 0]);
+        assert_eq!(widths(vec![Percentage(80), Percentage(80)], 10), vec![8, 2]);
+        // max and fill areas get nothing when the rest take all the room
+        assert_eq!(widths(vec![Fixed(5), Max(3), Fill(1)], 5), vec![5, 0, 0]);
+        assert_eq!(widths(vec![Fixed(3), Fill(1)], 0), vec![0, 0]);
+    }
+
+    #[test]
+    fn areas_are_placed_one_after_the_other() {
+        let layout = Layout::vertical(vec![Constraint::Fixed(1), Constraint::Fill(1)]);
+        assert_eq!(layout.split(Rect::new(2, 3, 4, 5)), vec![Rect::new(2, 3, 4, 1), Rect::new(2, 4, 4, 4)]);
+    }
+}
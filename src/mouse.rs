@@ -4,6 +4,8 @@ pub enum Mouse {
     Right,
     Middle,
     Release,
+    /// Moved with a button held down.
+    Drag,
     WheelUp,
     WheelDown
 }
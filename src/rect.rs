@@ -0,0 +1,51 @@
+/// A rectangle of cells, `x` and `y` being its top left corner.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Default)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Column just past the right edge.
+    pub fn right(&self) -> usize {
+        self.x + self.width
+    }
+
+    /// Row just below the bottom edge.
+    pub fn bottom(&self) -> usize {
+        self.y + self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        x >= self.x && x < self.right() && y >= self.y && y < self.bottom()
+    }
+
+    /// The area inside a border one cell wide.
+    pub fn inner(&self) -> Rect {
+        Rect::new(self.x + 1, self.y + 1, self.width.saturating_sub(2), self.height.saturating_sub(2))
+    }
+
+    /// The part covered by both rectangles, empty if they don't overlap.
+    pub fn intersection(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        Rect::new(x, y, right.saturating_sub(x), bottom.saturating_sub(y))
+    }
+}
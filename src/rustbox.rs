@@ -1,9 +1,16 @@
 extern crate gag;
 extern crate num_traits;
+extern crate unicode_normalization;
+extern crate unicode_segmentation;
+extern crate unicode_width;
+#[cfg(feature = "termbox")]
 extern crate termbox_sys as termbox;
+#[cfg(feature = "native")]
+extern crate libc;
 #[macro_use] extern crate bitflags;
 
 pub use self::style::{Style, RB_BOLD, RB_UNDERLINE, RB_REVERSE, RB_NORMAL};
+pub use self::style::{RB_ITALIC, RB_DIM, RB_BLINK, RB_STRIKETHROUGH, RB_OVERLINE, RB_DOUBLE_UNDERLINE};
 
 use std::error::Error;
 use std::fmt;
@@ -14,19 +21,38 @@ use std::ops::FnOnce;
 use std::sync::Mutex;
 
 use num_traits::FromPrimitive;
-use termbox::RawEvent;
-use termbox::RawCell;
 use std::os::raw::c_int;
 use gag::Hold;
 use std::time::Duration;
 
+use backend::{Backend, NullBackend, RawCell};
+#[cfg(feature = "termbox")]
+use backend::RawEvent;
+
+pub mod backend;
+pub mod border;
+pub mod buffer;
+pub mod color;
 pub mod keyboard;
+pub mod layout;
 pub mod mouse;
-
+pub mod rect;
+pub mod surface;
+pub mod text;
+pub mod unicode;
+pub mod widgets;
+
+pub use border::Border;
+pub use buffer::CellBuffer;
+pub use color::Color;
+pub use color::TRUECOLOR_DEFAULT;
 pub use keyboard::Modifiers;
 pub use keyboard::ExtendedKey;
 pub use keyboard::Key;
 pub use mouse::Mouse;
+pub use rect::Rect;
+pub use surface::Surface;
+pub use text::{Alignment, MarkupError, Paragraph, Span, StyledText};
 
 #[derive(Clone, Copy, Debug)]
 pub enum Event {
@@ -37,7 +63,28 @@ pub enum Event {
     NoEvent
 }
 
-#[derive(Clone, Copy, Debug)]
+/// A cell of the screen with decoded attributes.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Cell {
+    pub ch: char,
+    pub fg: Color,
+    pub bg: Color,
+    pub style: Style,
+}
+
+impl Cell {
+    /// Decodes a raw cell written in `mode`.
+    pub fn from_raw(raw: RawCell, mode: OutputMode) -> Cell {
+        Cell {
+            ch: char::from_u32(raw.ch).unwrap_or(' '),
+            fg: Color::from_attribute(raw.fg, mode),
+            bg: Color::from_attribute(raw.bg, mode),
+            style: Style::from_attributes(raw.fg, raw.bg, mode),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum InputMode {
     Current = 0x00,
 
@@ -53,101 +100,153 @@ pub enum InputMode {
     AltMouse = 0x06
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum OutputMode {
-    Normal = 0,
-    EightBit = 1,  // 256 Colors
-    // TrueColor = 2 ; needs to be compiled in
-    NoOutput = 10,
-}
-
-#[derive(Clone, Copy, PartialEq, Debug)]
-pub enum Color {
-    Black,
-    Red,
-    Green,
-    Yellow,
-    Blue,
-    Magenta,
-    Cyan,
-    White,
-    Byte(u16),
-    Default,
-}
-
-impl Color {
-    pub fn as_256color(&self) -> u16 {
-        match *self {
-            Color::Black => 0x10,
-            Color::Red => 0x01,
-            Color::Green => 0x02,
-            Color::Yellow => 0x03,
-            Color::Blue => 0x04,
-            Color::Magenta => 0x05,
-            Color::Cyan => 0x06,
-            Color::White => 0x07,
-            Color::Byte(b) => b,
-            Color::Default => panic!("Attempted to cast default color to byte"),
-        }
-    }
-
-    pub fn as_16color(&self) -> u16 {
-        match *self {
-            Color::Default => 0x00,
-            Color::Black => 0x10,
-            Color::Red => 0x01,
-            Color::Green => 0x02,
-            Color::Yellow => 0x03,
-            Color::Blue => 0x04,
-            Color::Magenta => 0x05,
-            Color::Cyan => 0x06,
-            Color::White => 0x0F,
-            Color::Byte(b) => panic!("Attempted to cast color byte {} to 16 color mode", b),
+impl InputMode {
+    pub fn from_code(code: c_int) -> Option<InputMode> {
+        match code {
+            0x00 => Some(InputMode::Current),
+            0x01 => Some(InputMode::Esc),
+            0x02 => Some(InputMode::Alt),
+            0x05 => Some(InputMode::EscMouse),
+            0x06 => Some(InputMode::AltMouse),
+            _ => None
         }
     }
 }
 
-impl Default for Color {
-    fn default() -> Color {
-        Color::Black
-    }
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputMode {
+    Normal = 0,
+    EightBit = 1,  // 256 Colors
+    TrueColor = 2, // 24-bit RGB, needs to be compiled into termbox
+    NoOutput = 10, // Leaves the terminal alone, see HeadlessBackend for tests
 }
 
 mod style {
     bitflags! {
         #[repr(C)]
-        flags Style: u16 {
-            const TB_NORMAL_COLOR = 0x000F,
+        flags Style: u32 {
+            // Wide enough for Black, which is 0x10 so that it isn't mistaken for the default
+            const TB_NORMAL_COLOR = 0x001F,
             const RB_BOLD = 0x0100,
+            const RB_ITALIC = 0x0200,
             const RB_UNDERLINE = 0x0400,
             const RB_REVERSE = 0x0800,
+            const RB_BLINK = 0x1000,
+            const RB_DIM = 0x2000,
+            const RB_STRIKETHROUGH = 0x4000,
+            const RB_OVERLINE = 0x8000,
+            // Above the 16 bits of a termbox cell
+            const RB_DOUBLE_UNDERLINE = 0x1_0000,
             const RB_NORMAL = 0x0000,
-            const TB_ATTRIB = RB_BOLD.bits | RB_UNDERLINE.bits | RB_REVERSE.bits,
+            const TB_ATTRIB = RB_BOLD.bits | RB_ITALIC.bits | RB_UNDERLINE.bits | RB_REVERSE.bits
+                | RB_BLINK.bits | RB_DIM.bits | RB_STRIKETHROUGH.bits | RB_OVERLINE.bits
+                | RB_DOUBLE_UNDERLINE.bits,
         }
     }
 
+    /// Where each style lives in a true color foreground cell. The top bit is
+    /// taken by `TRUECOLOR_DEFAULT`.
+    const TRUECOLOR_ATTRIBUTES: [(Style, u32); 7] = [
+        (RB_BOLD, 0x0100_0000),
+        (RB_UNDERLINE, 0x0200_0000),
+        (RB_REVERSE, 0x0400_0000),
+        (RB_ITALIC, 0x0800_0000),
+        (RB_BLINK, 0x1000_0000),
+        (RB_DIM, 0x2000_0000),
+        (RB_STRIKETHROUGH, 0x4000_0000),
+    ];
+
+    /// Styles that don't fit next to the foreground color go with the background.
+    const TRUECOLOR_BG_ATTRIBUTES: [(Style, u32); 2] = [
+        (RB_OVERLINE, 0x0100_0000),
+        (RB_DOUBLE_UNDERLINE, 0x0200_0000),
+    ];
+
+    /// What an unsupported style is drawn as instead, termbox has none of these.
+    /// Styles without an entry are dropped.
+    const FALLBACKS: [(Style, Style); 3] = [
+        (RB_DOUBLE_UNDERLINE, RB_UNDERLINE),
+        (RB_OVERLINE, RB_UNDERLINE),
+        (RB_STRIKETHROUGH, RB_DIM),
+    ];
+
     impl Style {
         pub fn from_color(color: super::Color) -> Style {
-            Style { bits: color.as_16color() & TB_NORMAL_COLOR.bits }
+            Style { bits: color.as_16color() as u32 & TB_NORMAL_COLOR.bits }
         }
 
         pub fn from_256color(color: super::Color) -> Style {
-            Style { bits: color.as_256color() }
+            Style { bits: color.as_256color() as u32 }
+        }
+
+        /// Replaces the attributes outside of `supported` by their fallbacks.
+        pub fn fallback(&self, supported: Style) -> Style {
+            let mut sty = *self & (supported | !TB_ATTRIB);
+            for &(flag, instead) in FALLBACKS.iter() {
+                if self.contains(flag) && !supported.contains(flag) {
+                    sty = sty | (instead & supported);
+                }
+            }
+            sty
+        }
+
+        /// Attribute bits for a true color cell, which keeps them above the 24 color bits.
+        pub fn as_truecolor_attributes(&self) -> u32 {
+            encode(*self, &TRUECOLOR_ATTRIBUTES)
+        }
+
+        /// Attribute bits kept above the 24 bits of a true color background.
+        pub fn as_truecolor_bg_attributes(&self) -> u32 {
+            encode(*self, &TRUECOLOR_BG_ATTRIBUTES)
+        }
+
+        /// Decodes the style of the cell attributes `fg` and `bg` written in `mode`.
+        pub fn from_attributes(fg: u32, bg: u32, mode: super::OutputMode) -> Style {
+            match mode {
+                super::OutputMode::TrueColor => {
+                    decode(fg, &TRUECOLOR_ATTRIBUTES) | decode(bg, &TRUECOLOR_BG_ATTRIBUTES)
+                },
+                _ => Style::from_bits_truncate(fg) & TB_ATTRIB,
+            }
         }
     }
-}
 
-const NIL_RAW_EVENT: RawEvent = RawEvent { etype: 0, key: 0, ch: 0, meta: 0, w: 0, h: 0, x: 0, y: 0 };
+    fn encode(sty: Style, table: &[(Style, u32)]) -> u32 {
+        let mut attrs = 0;
+        for &(flag, bit) in table.iter() {
+            if sty.contains(flag) {
+                attrs |= bit;
+            }
+        }
+        attrs
+    }
+
+    fn decode(attr: u32, table: &[(Style, u32)]) -> Style {
+        let mut sty = RB_NORMAL;
+        for &(flag, bit) in table.iter() {
+            if attr & bit != 0 {
+                sty = sty | flag;
+            }
+        }
+        sty
+    }
+}
 
 #[derive(Debug)]
 pub enum EventError {
    TermboxError,
+   /// The terminal was closed, reading from it gives end of file.
+   Hangup,
    Unknown(isize),
 }
 
 impl fmt::Display for EventError {
    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-      write!(fmt, "{}", self.to_string())
+      match *self {
+         EventError::TermboxError => write!(fmt, "Error in Termbox"),
+         EventError::Hangup => write!(fmt, "The terminal was closed"),
+         EventError::Unknown(n) => write!(fmt, "Unknown error in Termbox, error code {}", n),
+      }
    }
 }
 
@@ -155,6 +254,7 @@ impl Error for EventError {
    fn description(&self) -> &str {
       match *self {
          EventError::TermboxError => "Error in Termbox",
+         EventError::Hangup => "The terminal was closed",
          // I don't know how to format this without lifetime error.
          // EventError::Unknown(n) => &format!("There was an unknown error. Error code: {}", n),
          EventError::Unknown(_) => "Unknown error in Termbox",
@@ -177,21 +277,12 @@ impl FromPrimitive for EventError {
 
 pub type EventResult = Result<Event, EventError>;
 
+#[cfg(feature = "termbox")]
 fn unpack_key(event_key: u16, event_char: u32) -> Option<ExtendedKey> {
-    let mut modifiers = Modifiers::new();
-    let parsed_key = char::from_u32(event_char);
-    match parsed_key {
-        Some(c) => {
-            if c.is_ascii_uppercase() {
-                modifiers = Modifiers::new_all(false, true, false);
-                return Some(ExtendedKey::new(Key::Char(c), modifiers));
-            } else {
-                return Some(ExtendedKey::new(Key::Char(c), modifiers));
-            }
-        }
-        _ => {}
+    match char::from_u32(event_char) {
+        Some(c) => Some(ExtendedKey::new(Key::Char(c), Modifiers::new())),
+        None => Some(ExtendedKey::new(Key::Unknown(event_key), Modifiers::new())),
     }
-    return Some(ExtendedKey::new(Key::Unknown(event_key), Modifiers::new()));
 }
 
 /// Unpack a RawEvent to an Event
@@ -202,6 +293,7 @@ fn unpack_key(event_key: u16, event_char: u32) -> Option<ExtendedKey> {
 ///
 /// This is useful if you want to interpret the raw event data yourself, rather
 /// than having rustbox translate it to its own representation.
+#[cfg(feature = "termbox")]
 fn unpack_event(ev_type: c_int, ev: &RawEvent, raw: bool) -> EventResult {
     match ev_type {
         0 => Ok(Event::NoEvent),
@@ -209,17 +301,18 @@ fn unpack_event(ev_type: c_int, ev: &RawEvent, raw: bool) -> EventResult {
             if raw {
                 Event::KeyEventRaw(ev.meta, ev.key, ev.ch)
             } else {
-                // TODO: emod has alt modifier
-                // there is no shift modifier to check for so we simplify - check for uppercase
+                // shift is part of a character, termbox only reports it for special keys
                 let k = match ev.key {
                     0 => unpack_key(ev.key, ev.ch),
-                    // TODO: figure out how to get ctrl+shift shortcuts
                     a => ExtendedKey::from_code(a),
                 };
 
 
                 if let Some(key) = k {
-                    let mut actual_key = key;
+                    // meta carries termbox2's `TB_MOD_*` bits: alt (set in
+                    // `InputMode::Alt` when ESC precedes the key) and the ctrl and
+                    // shift state of special keys
+                    let mut actual_key = key.with_modifiers(Modifiers::from_meta(ev.meta));
                     actual_key.raw_emod = ev.meta;
                     actual_key.raw_key = ev.key;
                     actual_key.raw_ch = ev.ch;
@@ -230,7 +323,11 @@ fn unpack_event(ev_type: c_int, ev: &RawEvent, raw: bool) -> EventResult {
             }),
         2 => Ok(Event::ResizeEvent(ev.w, ev.h)),
         3 => {
-            let mouse = Mouse::from_code(ev.key).unwrap_or(Mouse::Left);
+            let mouse = if ev.meta & keyboard::MOD_MOTION != 0 {
+                Mouse::Drag
+            } else {
+                Mouse::from_code(ev.key).unwrap_or(Mouse::Left)
+            };
             Ok(Event::MouseEvent(mouse, ev.x, ev.y))
         },
         // `unwrap` is safe here because FromPrimitive for EventError only returns `Some`.
@@ -250,7 +347,14 @@ pub enum InitError {
 
 impl fmt::Display for InitError {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "{}", self.to_string())
+        match *self {
+            InitError::BufferStderrFailed(ref e) => write!(fmt, "Could not redirect stderr: {}", e),
+            InitError::AlreadyOpen => write!(fmt, "RustBox is already open"),
+            InitError::UnsupportedTerminal => write!(fmt, "Unsupported terminal"),
+            InitError::FailedToOpenTTy => write!(fmt, "Failed to open TTY"),
+            InitError::PipeTrapError => write!(fmt, "Pipe trap error"),
+            InitError::Unknown(n) => write!(fmt, "Unknown error from Termbox, error code {}", n),
+        }
     }
 }
 
@@ -291,25 +395,37 @@ impl FromPrimitive for InitError {
 
 #[allow(missing_copy_implementations)]
 pub struct RustBox {
+    // Does the actual terminal work, see the backend module. Declared first so
+    // the terminal is restored before buffered stderr is written out on drop.
+    backend: Box<dyn Backend>,
+
     // We only bother to redirect stderr for the moment, since it's used for panic!
     _stderr: Option<Hold>,
 
     // Store this so we know which colours to use
     output_mode: OutputMode,
 
-    // Used/obtained by methods that read from the terminal (or termbox's
+    input_mode: InputMode,
+
+    // Used/obtained by methods that read from the terminal (or the backend's
     // internal state). Termbox is only thread safe to the extent that one
     // thread can read while another writes; this lock prevents overlapping
     // reads.
     // See https://github.com/nsf/termbox/commit/493dc1395c91174e97658ff15fa2380227faf28f
     input_lock: Mutex<()>,
 
-    // Used/obtained by methods that write to the terminal (or termbox's
+    // Used/obtained by methods that write to the terminal (or the backend's
     // internal state). Termbox is only thread safe to the extent that one
     // thread can read while another writes; this lock prevents overlapping
     // writes.
     // See https://github.com/nsf/termbox/commit/493dc1395c91174e97658ff15fa2380227faf28f
     output_lock: Mutex<()>,
+
+    // Drawing outside of the top rectangle is dropped, see `push_clip`
+    clip_stack: Mutex<Vec<Rect>>,
+
+    // Text attributes the terminal can show, others fall back, see `Style::fallback`
+    supported_styles: Style,
 }
 
 #[derive(Clone, Copy,Debug)]
@@ -362,44 +478,51 @@ impl RustBox {
     /// use std::default::Default;
     /// let rb = RustBox::init(InitOptions { input_mode: rustbox::InputMode::Esc, ..Default::default() });
     /// ```
+    ///
+    /// The terminal is driven by the backend selected with cargo features, see
+    /// `init_with_backend` to pick one explicitly.
     pub fn init(opts: InitOptions) -> Result<RustBox, InitError> {
+        let backend: Box<dyn Backend> = if opts.output_mode != OutputMode::NoOutput {
+            backend::default_backend()?
+        } else {
+            Box::new(NullBackend)
+        };
+        RustBox::init_with_backend(backend, opts)
+    }
+
+    /// Initialize rustbox on top of an already initialized backend.
+    ///
+    /// ```no_run
+    /// # #[cfg(feature = "native")] {
+    /// use rustbox::RustBox;
+    /// use rustbox::backend::NativeBackend;
+    /// use std::default::Default;
+    /// let backend = NativeBackend::init().unwrap();
+    /// let rb = RustBox::init_with_backend(Box::new(backend), Default::default());
+    /// # }
+    /// ```
+    pub fn init_with_backend(backend: Box<dyn Backend>, opts: InitOptions) -> Result<RustBox, InitError> {
         let stderr = if opts.buffer_stderr {
-            Some(try!(Hold::stderr().map_err(|e| InitError::BufferStderrFailed(e))))
+            Some(Hold::stderr().map_err(InitError::BufferStderrFailed)?)
         } else {
             None
         };
 
         // Create the RustBox.
-        let mut rb = unsafe { 
-            if opts.output_mode != OutputMode::NoOutput {
-                match termbox::tb_init() {
-                    0 => RustBox {
-                        _stderr: stderr,
-                        output_mode: opts.output_mode,
-                        input_lock: Mutex::new(()),
-                        output_lock: Mutex::new(()),
-                    },
-                    res => {
-                        return Err(FromPrimitive::from_isize(res as isize).unwrap())
-                    }
-                }
-            } else {
-                RustBox {
-                    _stderr: stderr,
-                    output_mode: opts.output_mode,
-                    input_lock: Mutex::new(()),
-                    output_lock: Mutex::new(()),
-                }
-            }
+        let mut rb = RustBox {
+            backend,
+            _stderr: stderr,
+            output_mode: opts.output_mode,
+            input_mode: InputMode::Current,
+            input_lock: Mutex::new(()),
+            output_lock: Mutex::new(()),
+            clip_stack: Mutex::new(vec![]),
+            supported_styles: RB_NORMAL,
         };
+        rb.supported_styles = rb.backend.supported_styles();
 
-        /*
-        // TODO: set input mode
-        match opts.input_mode {
-            InputMode::Current=> (),
-            _ => rb.set_input_mode(opts.input_mode),
-        }
-        */
+        // With `InputMode::Current` this asks the backend which mode it is in
+        rb.set_input_mode(opts.input_mode);
 
         match opts.output_mode {
             OutputMode::Normal => (),
@@ -411,245 +534,573 @@ impl RustBox {
     }
 
     pub fn width(&self) -> usize {
-        if self.output_mode == OutputMode::NoOutput {
-            return 0;
-        }
         let _lock = self.output_lock.lock();
 
-        unsafe { termbox::tb_width() as usize }
+        self.backend.width()
     }
 
     pub fn height(&self) -> usize {
-        if self.output_mode == OutputMode::NoOutput {
-            return 0;
-        }
         let _lock = self.output_lock.lock();
 
-        unsafe { termbox::tb_height() as usize }
+        self.backend.height()
     }
 
     pub fn clear(&self) {
-        if self.output_mode == OutputMode::NoOutput {
-            return;
-        }
         let _lock = self.output_lock.lock();
 
-        unsafe { termbox::tb_clear_buffer() }
+        self.backend.clear()
     }
 
     pub fn clear_screen(&self) {
-        if self.output_mode == OutputMode::NoOutput {
-            return;
-        }
         let _lock = self.output_lock.lock();
 
-        unsafe { termbox::tb_clear_screen() }
+        self.backend.clear_screen()
     }
 
     pub fn present(&self) {
-        if self.output_mode == OutputMode::NoOutput {
-            return;
-        }
         let _lock = self.output_lock.lock();
 
-        unsafe { termbox::tb_render() }
+        self.backend.present()
     }
 
     pub fn set_cursor(&self, x: isize, y: isize) {
-        if self.output_mode == OutputMode::NoOutput {
-            return;
-        }
         let _lock = self.output_lock.lock();
 
-        unsafe { termbox::tb_set_cursor(x as c_int, y as c_int) }
+        self.backend.set_cursor(x, y)
     }
 
+    /// Prints `s` starting at `x`, `y`, advancing by the display width of each
+    /// grapheme cluster so wide characters take up two cells.
     pub fn print(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, s: &str) {
-        if self.output_mode == OutputMode::NoOutput {
-            return;
-        }
+        self.print_clipped(x, y, sty, fg, bg, s);
+    }
+
+    /// Like `print`, returning the number of columns that were actually written
+    /// inside the screen and the active clip rectangle.
+    pub fn print_clipped(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, s: &str) -> usize {
         let _lock = self.output_lock.lock();
 
-        let fg_int;
-        let bg_int;
+        let area = self.drawable_area();
+        let (fg_int, bg_int) = self.cell_attributes(sty, fg, bg);
+        let mut column = x;
+        let mut written = 0;
+        for (grapheme, width) in unicode::graphemes(s) {
+            let (ch, marks) = unicode::cell_content(grapheme);
+            written += self.put_cluster(&area, column, y, ch, &marks, width, fg_int, bg_int);
+            column += width;
+        }
+        written
+    }
 
-        match self.output_mode {
-            // 256 color mode
-            OutputMode::EightBit => {
-                fg_int = Style::from_256color(fg) | (sty & style::TB_ATTRIB);
-                bg_int = Style::from_256color(bg);
-            },
+    /// Prints at most `max_width` columns of `s`, also stopping at the edge of the
+    /// screen or clip rectangle. If `s` doesn't fit, its end is replaced by
+    /// `ellipsis`. Returns the number of columns written.
+    #[allow(clippy::too_many_arguments)]
+    pub fn print_truncated(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, s: &str,
+                           max_width: usize, ellipsis: Option<&str>) -> usize {
+        let available = {
+            let _lock = self.output_lock.lock();
+            self.drawable_area().right().saturating_sub(x)
+        };
+        let text = unicode::truncate(s, max_width.min(available), ellipsis);
+        self.print_clipped(x, y, sty, fg, bg, &text)
+    }
 
-            // 16 color mode
-            _ => {
-                fg_int = Style::from_color(fg) | (sty & style::TB_ATTRIB);
-                bg_int = Style::from_color(bg);
+    /// Prints spans one after the other, each in its own style and colors.
+    /// Returns the number of columns written, like `print_clipped`.
+    pub fn print_spans(&self, x: usize, y: usize, spans: &[Span]) -> usize {
+        let _lock = self.output_lock.lock();
+
+        let area = self.drawable_area();
+        let mut column = x;
+        let mut written = 0;
+        for span in spans {
+            let (fg_int, bg_int) = self.cell_attributes(span.style, span.fg, span.bg);
+            for (grapheme, width) in unicode::graphemes(&span.text) {
+                let (ch, marks) = unicode::cell_content(grapheme);
+                written += self.put_cluster(&area, column, y, ch, &marks, width, fg_int, bg_int);
+                column += width;
             }
         }
+        written
+    }
 
-        for (i, ch) in s.chars().enumerate() {
-            unsafe {
-                self.change_cell(x+i, y, ch as u32, fg_int.bits(), bg_int.bits());
+    /// Prints a word-wrapped paragraph with its top left corner at `x`, `y`.
+    /// Returns the number of lines the whole text takes up, so callers can
+    /// scroll with `Paragraph::scroll`.
+    pub fn print_paragraph(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color,
+                           paragraph: &Paragraph) -> usize {
+        for (row, words) in paragraph.visible_lines().into_iter().enumerate() {
+            for (column, word) in words {
+                self.print_clipped(x + column, y + row, sty, fg, bg, word);
             }
         }
+        paragraph.line_count()
     }
 
     pub fn print_char(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, ch: char) {
-        if self.output_mode == OutputMode::NoOutput {
-            return;
+        let _lock = self.output_lock.lock();
+
+        let area = self.drawable_area();
+        let (fg_int, bg_int) = self.cell_attributes(sty, fg, bg);
+        self.put_char(&area, x, y, ch, unicode::char_width(ch), fg_int, bg_int);
+    }
+
+    /// Writes a character taking `width` columns if it fits entirely into `area`,
+    /// returning the number of columns written. The cells covered by the right
+    /// half of a wide character are set to `'\0'`.
+    #[allow(clippy::too_many_arguments)]
+    fn put_char(&self, area: &Rect, x: usize, y: usize, ch: char, width: usize, fg: u32, bg: u32) -> usize {
+        self.put_cluster(area, x, y, ch, "", width, fg, bg)
+    }
+
+    /// Like `put_char` for a grapheme cluster, `ch` followed by combining `marks`.
+    #[allow(clippy::too_many_arguments)]
+    fn put_cluster(&self, area: &Rect, x: usize, y: usize, ch: char, marks: &str, width: usize,
+                   fg: u32, bg: u32) -> usize {
+        if width == 0 || !area.contains(x, y) || !area.contains(x + width - 1, y) {
+            return 0;
         }
+        if marks.is_empty() {
+            self.backend.change_cell(x, y, ch as u32, fg, bg);
+        } else {
+            self.backend.change_cluster(x, y, ch as u32, marks, fg, bg);
+        }
+        for i in 1..width {
+            self.backend.change_cell(x + i, y, 0, fg, bg);
+        }
+        width
+    }
+
+    /// Draws a horizontal line `length` columns long, joining it with the lines
+    /// it crosses or ends on.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_hline(&self, x: usize, y: usize, length: usize, sty: Style, fg: Color, bg: Color,
+                      border: Border) {
         let _lock = self.output_lock.lock();
 
-        let fg_int;
-        let bg_int;
+        let area = self.drawable_area();
+        let (fg_int, bg_int) = self.cell_attributes(sty, fg, bg);
+        for i in 0..length {
+            let sides = [false, i + 1 < length, false, i > 0];
+            self.put_line(&area, x + i, y, sides, border, fg_int, bg_int);
+        }
+    }
+
+    /// Draws a vertical line `length` rows long, joining it with the lines it
+    /// crosses or ends on.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_vline(&self, x: usize, y: usize, length: usize, sty: Style, fg: Color, bg: Color,
+                      border: Border) {
+        let _lock = self.output_lock.lock();
+
+        let area = self.drawable_area();
+        let (fg_int, bg_int) = self.cell_attributes(sty, fg, bg);
+        for i in 0..length {
+            let sides = [i > 0, false, i + 1 < length, false];
+            self.put_line(&area, x, y + i, sides, border, fg_int, bg_int);
+        }
+    }
+
+    /// Draws the outline of `rect`, leaving its inside alone.
+    pub fn draw_rect(&self, rect: Rect, sty: Style, fg: Color, bg: Color, border: Border) {
+        if rect.width < 2 || rect.height < 2 {
+            self.draw_hline(rect.x, rect.y, rect.width, sty, fg, bg, border);
+            return self.draw_vline(rect.x, rect.y, rect.height, sty, fg, bg, border);
+        }
+        let _lock = self.output_lock.lock();
+
+        let area = self.drawable_area();
+        let (fg_int, bg_int) = self.cell_attributes(sty, fg, bg);
+        let (right, bottom) = (rect.right() - 1, rect.bottom() - 1);
+        for x in rect.x + 1..right {
+            self.put_line(&area, x, rect.y, border::HORIZONTAL, border, fg_int, bg_int);
+            self.put_line(&area, x, bottom, border::HORIZONTAL, border, fg_int, bg_int);
+        }
+        for y in rect.y + 1..bottom {
+            self.put_line(&area, rect.x, y, border::VERTICAL, border, fg_int, bg_int);
+            self.put_line(&area, right, y, border::VERTICAL, border, fg_int, bg_int);
+        }
+        self.put_line(&area, rect.x, rect.y, border::TOP_LEFT, border, fg_int, bg_int);
+        self.put_line(&area, right, rect.y, border::TOP_RIGHT, border, fg_int, bg_int);
+        self.put_line(&area, rect.x, bottom, border::BOTTOM_LEFT, border, fg_int, bg_int);
+        self.put_line(&area, right, bottom, border::BOTTOM_RIGHT, border, fg_int, bg_int);
+    }
+
+    /// Clears `rect` and draws its outline, with `title` embedded in the top
+    /// border and shortened with an ellipsis if it doesn't fit.
+    pub fn draw_box(&self, rect: Rect, sty: Style, fg: Color, bg: Color, border: Border,
+                    title: Option<&str>) {
+        {
+            let _lock = self.output_lock.lock();
+
+            let area = self.drawable_area().intersection(&rect.inner());
+            let (fg_int, bg_int) = self.cell_attributes(sty, fg, bg);
+            for y in area.y..area.bottom() {
+                for x in area.x..area.right() {
+                    self.put_char(&area, x, y, ' ', 1, fg_int, bg_int);
+                }
+            }
+        }
+        self.draw_rect(rect, sty, fg, bg, border);
+        if let Some(title) = title {
+            let ellipsis = if border == Border::Ascii { "..." } else { "…" };
+            self.print_truncated(rect.x + 1, rect.y, sty, fg, bg, &format!(" {} ", title),
+                                 rect.width.saturating_sub(2), Some(ellipsis));
+        }
+    }
+
+    /// Draws the part of a line passing through a cell, joined with whatever
+    /// line is already drawn there.
+    #[allow(clippy::too_many_arguments)]
+    fn put_line(&self, area: &Rect, x: usize, y: usize, sides: border::Sides, border: Border,
+                fg: u32, bg: u32) {
+        if !area.contains(x, y) {
+            return;
+        }
+        let existing = self.backend.get_cell(x, y)
+            .and_then(|cell| char::from_u32(cell.ch))
+            .unwrap_or(' ');
+        self.put_char(area, x, y, border.join(existing, sides), 1, fg, bg);
+    }
+
+    /// A surface covering the whole screen, to draw with local coordinates.
+    pub fn surface(&self) -> Surface<'_> {
+        Surface::new(self, Rect::new(0, 0, self.width(), self.height()))
+    }
+
+    /// A surface covering `area` of the screen.
+    pub fn region(&self, area: Rect) -> Surface<'_> {
+        Surface::new(self, area)
+    }
+
+    /// Restricts drawing to `rect` until the matching `pop_clip`. Clip rectangles
+    /// nest, so the effective one is the intersection with the enclosing ones.
+    pub fn push_clip(&self, rect: Rect) {
+        let mut stack = self.clip_stack.lock().unwrap();
+        let clip = match stack.last() {
+            Some(top) => top.intersection(&rect),
+            None => rect,
+        };
+        stack.push(clip);
+    }
 
+    /// Removes the innermost clip rectangle and returns it.
+    pub fn pop_clip(&self) -> Option<Rect> {
+        self.clip_stack.lock().unwrap().pop()
+    }
+
+    /// The effective clip rectangle, if any.
+    pub fn clip_rect(&self) -> Option<Rect> {
+        self.clip_stack.lock().unwrap().last().cloned()
+    }
+
+    /// The part of the screen that can be drawn to, given the active clip
+    /// rectangle. Expects the output lock to be held.
+    fn drawable_area(&self) -> Rect {
+        let screen = Rect::new(0, 0, self.backend.width(), self.backend.height());
+        match self.clip_rect() {
+            Some(clip) => screen.intersection(&clip),
+            None => screen,
+        }
+    }
+
+    /// Number of columns `s` takes up when printed.
+    pub fn text_width(s: &str) -> usize {
+        unicode::text_width(s)
+    }
+
+    /// Encodes style and colors as the foreground and background cell attributes
+    /// for the current output mode.
+    fn cell_attributes(&self, sty: Style, fg: Color, bg: Color) -> (u32, u32) {
+        let sty = sty.fallback(self.supported_styles);
         match self.output_mode {
+            OutputMode::TrueColor => {
+                (fg.as_truecolor() | sty.as_truecolor_attributes(),
+                 bg.as_truecolor() | sty.as_truecolor_bg_attributes())
+            },
+
             // 256 color mode
             OutputMode::EightBit => {
-                fg_int = Style::from_256color(fg) | (sty & style::TB_ATTRIB);
-                bg_int = Style::from_256color(bg);
+                ((Style::from_256color(fg) | (sty & style::TB_ATTRIB)).bits(),
+                 Style::from_256color(bg).bits())
             },
 
             // 16 color mode
             _ => {
-                fg_int = Style::from_color(fg) | (sty & style::TB_ATTRIB);
-                bg_int = Style::from_color(bg);
+                ((Style::from_color(fg) | (sty & style::TB_ATTRIB)).bits(),
+                 Style::from_color(bg).bits())
             }
         }
-        unsafe {
-            self.change_cell(x, y, ch as u32, fg_int.bits(), bg_int.bits());
+    }
+
+    /// Encodes a cell for the back buffer in the current output mode.
+    fn encode_cell(&self, cell: &Cell) -> RawCell {
+        let (fg, bg) = self.cell_attributes(cell.style, cell.fg, cell.bg);
+        RawCell { ch: cell.ch as u32, fg, bg }
+    }
+
+    /// Reads a cell back from the buffer that is drawn to, `None` when `x`, `y` is
+    /// off screen.
+    pub fn get_cell(&self, x: usize, y: usize) -> Option<Cell> {
+        let _lock = self.output_lock.lock();
+
+        self.backend.get_cell(x, y).map(|raw| Cell::from_raw(raw, self.output_mode))
+    }
+
+    /// A copy of the whole buffer that is drawn to, for bulk reads and edits.
+    /// Changed cells are written back when the returned `CellBuffer` is dropped;
+    /// output is locked until then.
+    ///
+    /// ```no_run
+    /// use rustbox::{Color, RustBox, RB_NORMAL};
+    /// use std::default::Default;
+    /// let rb = RustBox::init(Default::default()).unwrap();
+    ///
+    /// // dim everything behind a modal
+    /// let mut buffer = rb.cell_buffer();
+    /// for cell in buffer.iter_mut() {
+    ///     cell.fg = Color::Byte(0xf0);
+    ///     cell.style = RB_NORMAL;
+    /// }
+    /// ```
+    pub fn cell_buffer(&self) -> CellBuffer<'_> {
+        let lock = self.output_lock.lock();
+        let (width, height) = (self.backend.width(), self.backend.height());
+        let mut cells = Vec::with_capacity(width * height);
+        for y in 0..height {
+            for x in 0..width {
+                let raw = self.backend.get_cell(x, y).unwrap_or(RawCell::blank(self.output_mode));
+                cells.push(Cell::from_raw(raw, self.output_mode));
+            }
         }
+        CellBuffer::new(self, lock, width, height, cells)
     }
 
     pub fn poll_event(&self, raw: bool) -> EventResult {
-        if self.output_mode == OutputMode::NoOutput {
-            return Ok(Event::NoEvent);
-        }
         let _lock = self.input_lock.lock();
-        let mut ev = NIL_RAW_EVENT;
-        let rc = unsafe {
-            termbox::tb_poll_event(&mut ev)
-        };
-        unpack_event(rc, &ev, raw)
+
+        self.backend.poll_event(raw)
     }
 
     pub fn peek_event(&self, timeout: Duration, raw: bool) -> EventResult {
-        if self.output_mode == OutputMode::NoOutput {
-            return Ok(Event::NoEvent);
-        }
         let _lock = self.input_lock.lock();
-        let mut ev = NIL_RAW_EVENT;
-        let rc = unsafe {
-            termbox::tb_peek_event(&mut ev, (timeout.as_secs() * 1000 + timeout.subsec_nanos() as u64 / 1000000) as c_int)
-        };
-        unpack_event(rc, &ev, raw)
+
+        self.backend.peek_event(timeout, raw)
     }
 
-    pub unsafe fn change_cell(&self, x: usize, y: usize, ch: u32, fg: u16, bg: u16) {
-        if self.output_mode == OutputMode::NoOutput {
-            return;
+    /// Writes a single cell with raw attributes. In `OutputMode::TrueColor` the
+    /// attributes are `0xRRGGBB` colors with style bits on top, otherwise they are the
+    /// 16 bit termbox values.
+    ///
+    /// Cells outside the screen or the active clip rectangle are dropped.
+    ///
+    /// # Safety
+    ///
+    /// The attributes are passed on unchecked, they must be encoded for the current
+    /// output mode.
+    pub unsafe fn change_cell(&self, x: usize, y: usize, ch: u32, fg: u32, bg: u32) {
+        if self.drawable_area().contains(x, y) {
+            self.backend.change_cell(x, y, ch, fg, bg);
         }
-        let mut cell = RawCell{
-            ch: ch,
-            fg: fg,
-            bg: bg,
-        };
-        termbox::tb_cell(x as i32, y as i32, &mut cell);
     }
 
     pub fn hide_cursor(&self) {
-        if self.output_mode == OutputMode::NoOutput {
-            return;
-        }
-        unsafe {
-            termbox::tb_hide_cursor();
-        }
+        self.backend.hide_cursor();
     }
 
     pub fn show_cursor(&self) {
-        if self.output_mode == OutputMode::NoOutput {
-            return;
-        }
-        unsafe {
-            termbox::tb_show_cursor();
-        }
+        self.backend.show_cursor();
     }
 
     pub fn enable_mouse(&self) {
-        if self.output_mode == OutputMode::NoOutput {
-            return;
-        }
-        unsafe {
-            termbox::tb_enable_mouse();
-        }
+        self.backend.enable_mouse();
     }
 
     pub fn disable_mouse(&self) {
-        if self.output_mode == OutputMode::NoOutput {
-            return;
-        }
-        unsafe {
-            termbox::tb_disable_mouse();
-        }
+        self.backend.disable_mouse();
     }
 
-    /*
-    pub fn set_input_mode(&self, mode: InputMode) {
+    /// Selects how ESC is treated and whether mouse events are reported.
+    /// `InputMode::Current` leaves the mode as is and just refreshes `input_mode()`.
+    pub fn set_input_mode(&mut self, mode: InputMode) {
         let _lock = self.output_lock.lock();
 
-        unsafe {
-            termbox::tb_select_input_mode(mode as c_int);
-        }
+        self.input_mode = self.backend.select_input_mode(mode);
     }
-    */
 
+    pub fn input_mode(&self) -> InputMode {
+        self.input_mode
+    }
+
+    /// Selects the output mode. Falls back to `OutputMode::EightBit` when true color
+    /// is requested but the backend doesn't support it.
     pub fn set_output_mode(&mut self, mode: OutputMode) {
         let _lock = self.output_lock.lock();
 
         self.output_mode = mode;
         if mode != OutputMode::NoOutput {
-            unsafe {
-                termbox::tb_select_output_mode(mode as c_int);
-            }
+            self.output_mode = self.backend.select_output_mode(mode);
         }
     }
 
+    pub fn output_mode(&self) -> OutputMode {
+        self.output_mode
+    }
+
+    /// Checks whether `OutputMode::TrueColor` can be selected: for termbox whether
+    /// it was compiled with it, for the native backend whether the terminal
+    /// announces it in `COLORTERM`.
+    pub fn supports_truecolor(&self) -> bool {
+        self.output_mode != OutputMode::NoOutput && self.backend.supports_truecolor()
+    }
+
+    /// Text attributes the terminal is assumed to show, as reported by the backend.
+    pub fn supported_styles(&self) -> Style {
+        self.supported_styles
+    }
+
+    /// Overrides the text attributes the terminal can show, for terminals known
+    /// to lack some. Unsupported attributes are drawn with a fallback, a double
+    /// underline or overline as a plain underline and strikethrough as dim, or
+    /// left out.
+    pub fn set_supported_styles(&mut self, styles: Style) {
+        self.supported_styles = styles & style::TB_ATTRIB;
+    }
+
     /// Convenience method to lock all (both input/output) access to
-    /// Rustbox, shutdown termbox itself, and then defer to the caller (via F,
-    /// while access is still locked). Once F completes, termbox is started and
-    /// the locks are released.
+    /// Rustbox, shutdown the backend, and then defer to the caller (via F,
+    /// while access is still locked). Once F completes, the backend is started
+    /// and the locks are released.
     pub fn suspend<F>(&self, func: F)
-        where F: FnOnce() -> ()
+        where F: FnOnce()
     {
         // Lock I/O until we've resumed.
         let _input_lock = self.input_lock.lock();
         let _output_lock = self.output_lock.lock();
 
-        if self.output_mode != OutputMode::NoOutput {
-            unsafe {
-                termbox::tb_shutdown();
-            }
-        }
+        self.backend.suspend();
 
         func();
 
+        self.backend.resume();
+        // a fresh terminal starts out in its default modes
+        self.backend.select_input_mode(self.input_mode);
         if self.output_mode != OutputMode::NoOutput {
-            unsafe {
-                termbox::tb_init();
-            }
+            self.backend.select_output_mode(self.output_mode);
         }
     }
 }
 
-impl Drop for RustBox {
-    fn drop(&mut self) {
-        if self.output_mode != OutputMode::NoOutput {
-            // Since only one instance of the RustBox is ever accessible, we should not
-            // need to do this atomically.
-            // Note: we should definitely have RUSTBOX_RUNNING = true here.
-            unsafe {
-                termbox::tb_shutdown();
-            }
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "termbox")]
+    use super::unpack_event;
+    #[cfg(feature = "termbox")]
+    use backend::{RawEvent, NIL_RAW_EVENT};
+    #[cfg(feature = "termbox")]
+    use keyboard::{codes, MOD_ALT, MOD_CTRL, MOD_MOTION, MOD_SHIFT};
+    #[cfg(feature = "termbox")]
+    use {Event, Key, Modifiers, Mouse};
+    use backend::{HeadlessBackend, HeadlessScreen};
+    use std::default::Default;
+    use {Color, Rect, RustBox, RB_NORMAL};
+
+    fn headless(width: usize, height: usize) -> (RustBox, HeadlessScreen) {
+        let backend = HeadlessBackend::new(width, height);
+        let screen = backend.screen();
+        (RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap(), screen)
+    }
+
+    #[cfg(feature = "termbox")]
+    fn key(meta: u8, code: u16, ch: char) -> Option<(Key, Modifiers)> {
+        let ev = RawEvent { etype: 1, meta, key: code, ch: ch as u32, ..NIL_RAW_EVENT };
+        match unpack_event(1, &ev, false) {
+            Ok(Event::KeyEvent(key)) => Some((key.key, key.modifiers)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "termbox")]
+    fn termbox_modifiers() {
+        let mods = Modifiers::new_all;
+        assert_eq!(key(MOD_CTRL, codes::TB_KEY_ARROW_LEFT, '\0'), Some((Key::Left, mods(true, false, false))));
+        assert_eq!(key(MOD_SHIFT, codes::TB_KEY_ARROW_UP, '\0'), Some((Key::Up, mods(false, true, false))));
+        assert_eq!(key(MOD_CTRL | MOD_SHIFT, codes::TB_KEY_END, '\0'), Some((Key::End, mods(true, true, false))));
+        assert_eq!(key(MOD_ALT, codes::TB_KEY_ENTER, '\0'), Some((Key::Enter, mods(false, false, true))));
+        assert_eq!(key(MOD_SHIFT, codes::TB_KEY_TAB, '\0'), Some((Key::BackTab, Modifiers::new())));
+        // shift is part of the character
+        assert_eq!(key(0, 0, 'A'), Some((Key::Char('A'), Modifiers::new())));
+        assert_eq!(key(MOD_ALT, 0, 'x'), Some((Key::Char('x'), mods(false, false, true))));
+    }
+
+    #[test]
+    #[cfg(feature = "termbox")]
+    fn termbox_motion_is_a_drag() {
+        let ev = RawEvent { etype: 3, key: codes::TB_KEY_MOUSE_LEFT, x: 4, y: 2, ..NIL_RAW_EVENT };
+        match unpack_event(3, &ev, false) {
+            Ok(Event::MouseEvent(Mouse::Left, 4, 2)) => (),
+            _ => panic!(),
         }
+        let ev = RawEvent { meta: MOD_MOTION, ..ev };
+        match unpack_event(3, &ev, false) {
+            Ok(Event::MouseEvent(Mouse::Drag, 4, 2)) => (),
+            _ => panic!(),
+        }
+    }
+
+    #[test]
+    fn nested_clips_intersect() {
+        let (rb, screen) = headless(8, 3);
+        rb.push_clip(Rect::new(1, 0, 5, 3));
+        rb.push_clip(Rect::new(3, 1, 5, 1));
+        assert_eq!(rb.clip_rect(), Some(Rect::new(3, 1, 3, 1)));
+        rb.print(0, 1, RB_NORMAL, Color::Default, Color::Default, "abcdefgh");
+        rb.print(0, 0, RB_NORMAL, Color::Default, Color::Default, "abcdefgh");
+
+        // popping restores the enclosing clip
+        assert_eq!(rb.pop_clip(), Some(Rect::new(3, 1, 3, 1)));
+        rb.print(0, 2, RB_NORMAL, Color::Default, Color::Default, "abcdefgh");
+        assert_eq!(rb.pop_clip(), Some(Rect::new(1, 0, 5, 3)));
+        assert_eq!(rb.clip_rect(), None);
+
+        rb.present();
+        assert_eq!(screen.lines(), vec!["        ", "   def  ", " bcdef  "]);
+
+        // a clip outside the enclosing one leaves nothing to draw on
+        rb.push_clip(Rect::new(0, 0, 2, 2));
+        rb.push_clip(Rect::new(4, 0, 2, 2));
+        assert!(rb.clip_rect().unwrap().is_empty());
+        assert_eq!(rb.print_clipped(0, 0, RB_NORMAL, Color::Default, Color::Default, "xxxxxx"), 0);
+    }
+
+    #[test]
+    fn popping_an_empty_clip_stack() {
+        let (rb, screen) = headless(4, 1);
+        assert_eq!(rb.pop_clip(), None);
+        rb.push_clip(Rect::new(0, 0, 2, 1));
+        assert!(rb.pop_clip().is_some());
+        assert_eq!(rb.pop_clip(), None);
+        assert_eq!(rb.clip_rect(), None);
+        assert_eq!(rb.print_clipped(0, 0, RB_NORMAL, Color::Default, Color::Default, "abcd"), 4);
+        rb.present();
+        assert_eq!(screen.line(0).unwrap(), "abcd");
+    }
+
+    #[test]
+    fn wide_characters_straddling_the_clip_edge() {
+        let (rb, screen) = headless(8, 2);
+        rb.push_clip(Rect::new(1, 0, 4, 2));
+        // the first character starts left of the clip, the last one ends right of it
+        let written = rb.print_clipped(0, 0, RB_NORMAL, Color::Default, Color::Default, "日本語");
+        assert_eq!(written, 2);
+        // whole characters that fit are drawn, the one crossing the edge is left out
+        let written = rb.print_clipped(1, 1, RB_NORMAL, Color::Default, Color::Default, "a日本");
+        assert_eq!(written, 3);
+        rb.pop_clip();
+        rb.present();
+        assert_eq!(screen.line(0).unwrap(), "  本    ");
+        assert_eq!(screen.line(1).unwrap(), " a日    ");
     }
 }
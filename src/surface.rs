@@ -0,0 +1,280 @@
+//! Drawing into a region of the screen with local coordinates.
+
+use super::{Border, Color, Paragraph, Rect, RustBox, Span, Style};
+use layout::Layout;
+
+/// A rectangular region of a `RustBox` with its own coordinate system.
+///
+/// Coordinates passed to a surface are relative to its top left corner and
+/// everything drawn outside of it is dropped. Surfaces can be nested and split:
+///
+/// ```
+/// use rustbox::{Color, RustBox, RB_NORMAL};
+/// use rustbox::backend::HeadlessBackend;
+/// use std::default::Default;
+///
+/// let backend = HeadlessBackend::new(20, 5);
+/// let screen = backend.screen();
+/// let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+///
+/// let (sidebar, body) = rb.surface().split_at_column(5);
+/// sidebar.print(0, 0, RB_NORMAL, Color::White, Color::Default, "files and more");
+/// body.sub(1, 1, 10, 1).print(0, 0, RB_NORMAL, Color::White, Color::Default, "body");
+/// rb.present();
+///
+/// assert_eq!(screen.line(0).unwrap(), "files               ");
+/// assert_eq!(screen.line(1).unwrap(), "      body          ");
+/// ```
+#[derive(Clone, Copy)]
+pub struct Surface<'a> {
+    rb: &'a RustBox,
+    area: Rect,
+}
+
+impl<'a> Surface<'a> {
+    /// A surface covering `area` of the screen, in absolute coordinates.
+    pub fn new(rb: &'a RustBox, area: Rect) -> Surface<'a> {
+        Surface {
+            rb,
+            area,
+        }
+    }
+
+    pub fn rustbox(&self) -> &'a RustBox {
+        self.rb
+    }
+
+    /// The region covered on screen, in absolute coordinates.
+    pub fn area(&self) -> Rect {
+        self.area
+    }
+
+    pub fn width(&self) -> usize {
+        self.area.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.area.height
+    }
+
+    /// Prints `s` at local `x`, `y` and returns the number of columns written.
+    pub fn print(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, s: &str) -> usize {
+        self.rb.push_clip(self.area);
+        let written = self.rb.print_clipped(self.area.x + x, self.area.y + y, sty, fg, bg, s);
+        self.rb.pop_clip();
+        written
+    }
+
+    /// Like `RustBox::print_truncated`, in local coordinates.
+    #[allow(clippy::too_many_arguments)]
+    pub fn print_truncated(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, s: &str,
+                           max_width: usize, ellipsis: Option<&str>) -> usize {
+        self.rb.push_clip(self.area);
+        let written = self.rb.print_truncated(self.area.x + x, self.area.y + y, sty, fg, bg, s,
+                                              max_width, ellipsis);
+        self.rb.pop_clip();
+        written
+    }
+
+    /// Like `RustBox::print_spans`, in local coordinates.
+    pub fn print_spans(&self, x: usize, y: usize, spans: &[Span]) -> usize {
+        self.rb.push_clip(self.area);
+        let written = self.rb.print_spans(self.area.x + x, self.area.y + y, spans);
+        self.rb.pop_clip();
+        written
+    }
+
+    /// Like `RustBox::print_paragraph`, in local coordinates.
+    pub fn print_paragraph(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color,
+                           paragraph: &Paragraph) -> usize {
+        self.rb.push_clip(self.area);
+        let lines = self.rb.print_paragraph(self.area.x + x, self.area.y + y, sty, fg, bg, paragraph);
+        self.rb.pop_clip();
+        lines
+    }
+
+    pub fn print_char(&self, x: usize, y: usize, sty: Style, fg: Color, bg: Color, ch: char) {
+        self.rb.push_clip(self.area);
+        self.rb.print_char(self.area.x + x, self.area.y + y, sty, fg, bg, ch);
+        self.rb.pop_clip();
+    }
+
+    /// Like `RustBox::draw_hline`, in local coordinates.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_hline(&self, x: usize, y: usize, length: usize, sty: Style, fg: Color, bg: Color,
+                      border: Border) {
+        self.rb.push_clip(self.area);
+        self.rb.draw_hline(self.area.x + x, self.area.y + y, length, sty, fg, bg, border);
+        self.rb.pop_clip();
+    }
+
+    /// Like `RustBox::draw_vline`, in local coordinates.
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_vline(&self, x: usize, y: usize, length: usize, sty: Style, fg: Color, bg: Color,
+                      border: Border) {
+        self.rb.push_clip(self.area);
+        self.rb.draw_vline(self.area.x + x, self.area.y + y, length, sty, fg, bg, border);
+        self.rb.pop_clip();
+    }
+
+    /// Like `RustBox::draw_rect`, for a rectangle in local coordinates.
+    pub fn draw_rect(&self, rect: Rect, sty: Style, fg: Color, bg: Color, border: Border) {
+        self.rb.push_clip(self.area);
+        self.rb.draw_rect(self.screen_rect(rect), sty, fg, bg, border);
+        self.rb.pop_clip();
+    }
+
+    /// Like `RustBox::draw_box`, for a rectangle in local coordinates.
+    pub fn draw_box(&self, rect: Rect, sty: Style, fg: Color, bg: Color, border: Border,
+                    title: Option<&str>) {
+        self.rb.push_clip(self.area);
+        self.rb.draw_box(self.screen_rect(rect), sty, fg, bg, border, title);
+        self.rb.pop_clip();
+    }
+
+    /// Translates a rectangle in local coordinates to screen coordinates.
+    fn screen_rect(&self, rect: Rect) -> Rect {
+        Rect::new(self.area.x + rect.x, self.area.y + rect.y, rect.width, rect.height)
+    }
+
+    /// Sets every cell of the surface to `ch`.
+    pub fn fill(&self, sty: Style, fg: Color, bg: Color, ch: char) {
+        let line: String = (0..self.area.width).map(|_| ch).collect();
+        for y in 0..self.area.height {
+            self.print(0, y, sty, fg, bg, &line);
+        }
+    }
+
+    /// Moves the cursor to local `x`, `y`. It is hidden when that is outside of
+    /// the surface.
+    pub fn set_cursor(&self, x: isize, y: isize) {
+        if x < 0 || y < 0 || x as usize >= self.area.width || y as usize >= self.area.height {
+            self.rb.set_cursor(-1, -1);
+        } else {
+            self.rb.set_cursor(self.area.x as isize + x, self.area.y as isize + y);
+        }
+    }
+
+    /// Translates absolute screen coordinates, as found in mouse events, to local
+    /// ones. `None` when the point is outside of the surface.
+    pub fn to_local(&self, x: i16, y: i16) -> Option<(usize, usize)> {
+        if x < 0 || y < 0 || !self.area.contains(x as usize, y as usize) {
+            return None;
+        }
+        Some((x as usize - self.area.x, y as usize - self.area.y))
+    }
+
+    /// A nested surface at local `x`, `y`, cut down to fit into this one.
+    pub fn sub(&self, x: usize, y: usize, width: usize, height: usize) -> Surface<'a> {
+        let rect = Rect::new(self.area.x + x, self.area.y + y, width, height);
+        Surface::new(self.rb, self.area.intersection(&rect))
+    }
+
+    /// A nested surface for a rectangle in local coordinates.
+    pub fn sub_rect(&self, rect: Rect) -> Surface<'a> {
+        self.sub(rect.x, rect.y, rect.width, rect.height)
+    }
+
+    /// Nested surfaces for the areas of `layout`.
+    pub fn split(&self, layout: &Layout) -> Vec<Surface<'a>> {
+        let area = Rect::new(0, 0, self.area.width, self.area.height);
+        layout.split(area).into_iter().map(|rect| self.sub_rect(rect)).collect()
+    }
+
+    /// Splits into the columns left of `x` and the rest.
+    pub fn split_at_column(&self, x: usize) -> (Surface<'a>, Surface<'a>) {
+        let x = x.min(self.area.width);
+        (self.sub(0, 0, x, self.area.height),
+         self.sub(x, 0, self.area.width - x, self.area.height))
+    }
+
+    /// Splits into the rows above `y` and the rest.
+    pub fn split_at_row(&self, y: usize) -> (Surface<'a>, Surface<'a>) {
+        let y = y.min(self.area.height);
+        (self.sub(0, 0, self.area.width, y),
+         self.sub(0, y, self.area.width, self.area.height - y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use backend::{HeadlessBackend, HeadlessScreen};
+    use layout::{Constraint, Layout};
+    use std::default::Default;
+    use {Color, Rect, RustBox, RB_NORMAL};
+
+    fn headless(width: usize, height: usize) -> (RustBox, HeadlessScreen) {
+        let backend = HeadlessBackend::new(width, height);
+        let screen = backend.screen();
+        (RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap(), screen)
+    }
+
+    #[test]
+    fn sub_surfaces_are_cut_to_fit() {
+        let (rb, screen) = headless(8, 4);
+        let outer = rb.region(Rect::new(2, 1, 4, 2));
+        assert_eq!(outer.sub(1, 1, 10, 10).area(), Rect::new(3, 2, 3, 1));
+        // nested surfaces don't reach outside of the outer one
+        assert_eq!(outer.sub(1, 0, 2, 5).sub(0, 0, 8, 8).area(), Rect::new(3, 1, 2, 2));
+        // entirely outside, nothing is left to draw on
+        let outside = outer.sub(6, 0, 2, 2);
+        assert!(outside.area().is_empty());
+        assert_eq!(outside.print(0, 0, RB_NORMAL, Color::Default, Color::Default, "x"), 0);
+
+        assert_eq!(outer.sub(1, 1, 10, 10).print(0, 0, RB_NORMAL, Color::Default, Color::Default,
+                                                 "abcdef"), 3);
+        rb.present();
+        assert_eq!(screen.lines(), vec!["        ", "        ", "   abc  ", "        "]);
+    }
+
+    #[test]
+    fn drawing_is_clipped_to_the_surface() {
+        let (rb, screen) = headless(6, 3);
+        let surface = rb.region(Rect::new(1, 1, 3, 1));
+        surface.fill(RB_NORMAL, Color::Default, Color::Default, '.');
+        assert_eq!(surface.print(2, 0, RB_NORMAL, Color::Default, Color::Default, "xyz"), 1);
+        surface.print(0, 1, RB_NORMAL, Color::Default, Color::Default, "below");
+        // the clip is gone afterwards
+        assert_eq!(rb.clip_rect(), None);
+        rb.present();
+        assert_eq!(screen.lines(), vec!["      ", " ..x  ", "      "]);
+    }
+
+    #[test]
+    fn splits_cover_the_surface() {
+        let (rb, _) = headless(10, 4);
+        let surface = rb.region(Rect::new(1, 1, 8, 3));
+        let (left, right) = surface.split_at_column(3);
+        assert_eq!(left.area(), Rect::new(1, 1, 3, 3));
+        assert_eq!(right.area(), Rect::new(4, 1, 5, 3));
+        // splitting past the edge leaves an empty right side
+        let (left, right) = surface.split_at_column(20);
+        assert_eq!(left.area(), surface.area());
+        assert_eq!(right.width(), 0);
+        let (top, bottom) = surface.split_at_row(1);
+        assert_eq!(top.area(), Rect::new(1, 1, 8, 1));
+        assert_eq!(bottom.area(), Rect::new(1, 2, 8, 2));
+
+        let parts = surface.split(&Layout::horizontal(vec![Constraint::Fixed(2), Constraint::Fill(1)]));
+        let areas: Vec<Rect> = parts.iter().map(|part| part.area()).collect();
+        assert_eq!(areas, vec![Rect::new(1, 1, 2, 3), Rect::new(3, 1, 6, 3)]);
+    }
+
+    #[test]
+    fn coordinates_are_translated() {
+        let (rb, screen) = headless(8, 4);
+        let surface = rb.region(Rect::new(2, 1, 4, 2));
+        assert_eq!(surface.to_local(2, 1), Some((0, 0)));
+        assert_eq!(surface.to_local(5, 2), Some((3, 1)));
+        assert_eq!(surface.to_local(6, 2), None);
+        assert_eq!(surface.to_local(1, 1), None);
+        assert_eq!(surface.to_local(-1, 0), None);
+
+        surface.set_cursor(3, 1);
+        assert_eq!(screen.cursor(), Some((5, 2)));
+        surface.set_cursor(4, 1);
+        assert_eq!(screen.cursor(), None);
+        surface.set_cursor(-1, 0);
+        assert_eq!(screen.cursor(), None);
+    }
+}
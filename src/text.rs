@@ -0,0 +1,469 @@
+//! Text made of differently styled spans, and a markup to write it.
+
+use std::error::Error;
+use std::fmt;
+use std::ops::Deref;
+
+use super::{Color, Style, RB_NORMAL};
+use super::{RB_BOLD, RB_ITALIC, RB_UNDERLINE, RB_REVERSE, RB_BLINK, RB_DIM};
+use super::{RB_STRIKETHROUGH, RB_OVERLINE, RB_DOUBLE_UNDERLINE};
+use unicode;
+
+/// A piece of text printed with a single style.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+    pub fg: Color,
+    pub bg: Color,
+}
+
+impl Span {
+    /// Text in the terminal's default colors.
+    pub fn raw<S: Into<String>>(text: S) -> Span {
+        Span::styled(text, RB_NORMAL, Color::Default, Color::Default)
+    }
+
+    pub fn styled<S: Into<String>>(text: S, style: Style, fg: Color, bg: Color) -> Span {
+        Span {
+            text: text.into(),
+            style,
+            fg,
+            bg,
+        }
+    }
+
+    /// Number of columns the span takes up when printed.
+    pub fn width(&self) -> usize {
+        unicode::text_width(&self.text)
+    }
+}
+
+/// A line of spans, printed with `RustBox::print_spans`.
+///
+/// ```
+/// use rustbox::{Color, RustBox, StyledText, RB_BOLD};
+/// use rustbox::backend::HeadlessBackend;
+/// use std::default::Default;
+///
+/// let backend = HeadlessBackend::new(30, 1);
+/// let screen = backend.screen();
+/// let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+///
+/// let text = StyledText::from_markup("[bold red]Error:[/] file not found").unwrap();
+/// rb.print_spans(0, 0, &text);
+/// rb.present();
+///
+/// assert_eq!(screen.line(0).unwrap(), "Error: file not found         ");
+/// assert_eq!(screen.cell(0, 0).unwrap().style, RB_BOLD);
+/// assert_eq!(screen.cell(0, 0).unwrap().fg, Color::Red);
+/// assert_eq!(screen.cell(6, 0).unwrap().fg, Color::Default);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StyledText {
+    pub spans: Vec<Span>,
+}
+
+impl StyledText {
+    pub fn new() -> StyledText {
+        StyledText { spans: vec![] }
+    }
+
+    /// Appends a span, merging it into the last one if they are styled the same.
+    pub fn push(&mut self, span: Span) {
+        if span.text.is_empty() {
+            return;
+        }
+        if let Some(last) = self.spans.last_mut() {
+            if last.style == span.style && last.fg == span.fg && last.bg == span.bg {
+                last.text.push_str(&span.text);
+                return;
+            }
+        }
+        self.spans.push(span);
+    }
+
+    /// Number of columns the text takes up when printed.
+    pub fn width(&self) -> usize {
+        self.spans.iter().map(|span| span.width()).sum()
+    }
+
+    /// The text without any styling.
+    pub fn plain(&self) -> String {
+        self.spans.iter().map(|span| &span.text[..]).collect()
+    }
+
+    /// Parses text with inline style tags.
+    ///
+    /// A tag like `[bold red on white]` applies until the matching `[/]` and
+    /// tags nest. A tag holds style names (`bold`, `italic`, `underline`,
+    /// `double_underline`, `reverse`, `blink`, `dim`, `strike`, `overline`), a
+    /// foreground color and a background color after `on`. Colors are the
+    /// named ones, `default`, a palette index such as `color(208)`, or `#rrggbb`.
+    /// Write `[[` for a literal `[`.
+    pub fn from_markup(markup: &str) -> Result<StyledText, MarkupError> {
+        let mut text = StyledText::new();
+        let mut current = (RB_NORMAL, Color::Default, Color::Default);
+        let mut stack = vec![];
+        let mut plain = String::new();
+        let mut rest = markup;
+
+        while let Some(open) = rest.find('[') {
+            plain.push_str(&rest[..open]);
+            let after = &rest[open + 1..];
+            if let Some(after) = after.strip_prefix('[') {
+                plain.push('[');
+                rest = after;
+                continue;
+            }
+
+            let position = markup.len() - rest.len() + open;
+            let close = match after.find(']') {
+                Some(close) => close,
+                None => return Err(MarkupError::UnclosedTag(position)),
+            };
+            text.push(Span::styled(plain.split_off(0), current.0, current.1, current.2));
+
+            let tag = &after[..close];
+            if tag.starts_with('/') {
+                current = match stack.pop() {
+                    Some(previous) => previous,
+                    None => return Err(MarkupError::UnmatchedClose(position)),
+                };
+            } else {
+                stack.push(current);
+                current = apply_tag(tag, current, position)?;
+            }
+            rest = &after[close + 1..];
+        }
+        plain.push_str(rest);
+        text.push(Span::styled(plain, current.0, current.1, current.2));
+        Ok(text)
+    }
+}
+
+impl Deref for StyledText {
+    type Target = [Span];
+
+    fn deref(&self) -> &[Span] {
+        &self.spans
+    }
+}
+
+impl From<Vec<Span>> for StyledText {
+    fn from(spans: Vec<Span>) -> StyledText {
+        StyledText { spans }
+    }
+}
+
+/// Why markup couldn't be parsed, with the byte offset of the offending tag.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MarkupError {
+    UnclosedTag(usize),
+    UnmatchedClose(usize),
+    UnknownWord(usize, String),
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MarkupError::UnknownWord(position, ref word) => {
+                write!(fmt, "Unknown style '{}' in tag at {}", word, position)
+            },
+            MarkupError::UnclosedTag(position) => {
+                write!(fmt, "Tag without closing bracket at {}", position)
+            },
+            MarkupError::UnmatchedClose(position) => {
+                write!(fmt, "Closing tag without an open tag at {}", position)
+            },
+        }
+    }
+}
+
+impl Error for MarkupError {
+    fn description(&self) -> &str {
+        match *self {
+            MarkupError::UnclosedTag(_) => "Tag without closing bracket",
+            MarkupError::UnmatchedClose(_) => "Closing tag without an open tag",
+            MarkupError::UnknownWord(..) => "Unknown style or color in tag",
+        }
+    }
+}
+
+const STYLE_NAMES: [(&str, Style); 10] = [
+    ("bold", RB_BOLD),
+    ("italic", RB_ITALIC),
+    ("underline", RB_UNDERLINE),
+    ("double_underline", RB_DOUBLE_UNDERLINE),
+    ("reverse", RB_REVERSE),
+    ("blink", RB_BLINK),
+    ("dim", RB_DIM),
+    ("strike", RB_STRIKETHROUGH),
+    ("strikethrough", RB_STRIKETHROUGH),
+    ("overline", RB_OVERLINE),
+];
+
+const COLOR_NAMES: [(&str, Color); 9] = [
+    ("default", Color::Default),
+    ("black", Color::Black),
+    ("red", Color::Red),
+    ("green", Color::Green),
+    ("yellow", Color::Yellow),
+    ("blue", Color::Blue),
+    ("magenta", Color::Magenta),
+    ("cyan", Color::Cyan),
+    ("white", Color::White),
+];
+
+/// Applies the words of a tag on top of the enclosing style and colors.
+fn apply_tag(tag: &str, (mut sty, mut fg, mut bg): (Style, Color, Color), position: usize)
+             -> Result<(Style, Color, Color), MarkupError> {
+    let mut background = false;
+    for word in tag.split_whitespace() {
+        let lower = word.to_lowercase();
+        if lower == "on" {
+            background = true;
+        } else if let Some(color) = parse_color(&lower) {
+            if background {
+                bg = color;
+                background = false;
+            } else {
+                fg = color;
+            }
+        } else if let Some(&(_, flag)) = STYLE_NAMES.iter().find(|&&(name, _)| name == lower) {
+            sty = sty | flag;
+        } else {
+            return Err(MarkupError::UnknownWord(position, word.to_string()));
+        }
+    }
+    Ok((sty, fg, bg))
+}
+
+fn parse_color(word: &str) -> Option<Color> {
+    if let Some(&(_, color)) = COLOR_NAMES.iter().find(|&&(name, _)| name == word) {
+        return Some(color);
+    }
+    if word.starts_with('#') && word.len() == 7 && word.is_ascii() {
+        let channel = |i: usize| u8::from_str_radix(&word[i..i + 2], 16).ok();
+        return match (channel(1), channel(3), channel(5)) {
+            (Some(r), Some(g), Some(b)) => Some(Color::Rgb(r, g, b)),
+            _ => None,
+        };
+    }
+    if word.starts_with("color(") && word.ends_with(')') {
+        return word[6..word.len() - 1].parse::<u8>().ok().map(|b| Color::Byte(b as u16));
+    }
+    None
+}
+
+/// How the lines of a `Paragraph` are placed within its width.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Alignment {
+    Left,
+    Center,
+    Right,
+    /// Stretches the gaps between words so that lines fill the whole width,
+    /// except for the last line of each paragraph.
+    Justify,
+}
+
+/// Text word-wrapped to a width, printed with `RustBox::print_paragraph`.
+///
+/// Lines of `text` start new paragraphs, runs of whitespace within them are
+/// collapsed and words wider than a line are broken up.
+///
+/// ```
+/// use rustbox::text::{Alignment, Paragraph};
+///
+/// let mut paragraph = Paragraph::new("- a list item that wraps around", 14);
+/// paragraph.hanging_indent = 2;
+/// assert_eq!(paragraph.wrap(), vec!["- a list item", "  that wraps", "  around"]);
+///
+/// paragraph.alignment = Alignment::Justify;
+/// assert_eq!(paragraph.wrap(), vec!["-  a list item", "  that   wraps", "  around"]);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Paragraph<'a> {
+    pub text: &'a str,
+    pub width: usize,
+    pub alignment: Alignment,
+    /// Columns every line but the first of a paragraph is indented by.
+    pub hanging_indent: usize,
+    /// Number of wrapped lines to leave out at the top, for scrolling.
+    pub scroll: usize,
+}
+
+/// A wrapped line, its words with their widths.
+struct Line<'a> {
+    indent: usize,
+    words: Vec<(&'a str, usize)>,
+    last: bool,
+}
+
+impl<'a> Line<'a> {
+    fn new(indent: usize) -> Line<'a> {
+        Line { indent, words: vec![], last: false }
+    }
+
+    fn width(&self) -> usize {
+        let words: usize = self.words.iter().map(|&(_, width)| width).sum();
+        words + self.words.len().saturating_sub(1)
+    }
+}
+
+impl<'a> Paragraph<'a> {
+    /// Left aligned text without indentation.
+    pub fn new(text: &'a str, width: usize) -> Paragraph<'a> {
+        Paragraph {
+            text,
+            width,
+            alignment: Alignment::Left,
+            hanging_indent: 0,
+            scroll: 0,
+        }
+    }
+
+    /// Number of lines the text takes up once wrapped, including scrolled ones.
+    pub fn line_count(&self) -> usize {
+        self.lines().len()
+    }
+
+    /// The wrapped lines as they are printed, without trailing spaces.
+    pub fn wrap(&self) -> Vec<String> {
+        self.lines().iter().map(|line| {
+            let mut out = String::new();
+            for (column, word) in self.layout(line) {
+                let width = unicode::text_width(&out);
+                out.extend((width..column).map(|_| ' '));
+                out.push_str(word);
+            }
+            out
+        }).collect()
+    }
+
+    /// The words of each visible line with the column they start at.
+    pub fn visible_lines(&self) -> Vec<Vec<(usize, &'a str)>> {
+        self.lines().iter().skip(self.scroll).map(|line| self.layout(line)).collect()
+    }
+
+    /// Columns available for words on a line indented by `indent`, never less
+    /// than one so that wrapping makes progress.
+    fn available(&self, indent: usize) -> usize {
+        self.width.saturating_sub(indent).max(1)
+    }
+
+    fn lines(&self) -> Vec<Line<'a>> {
+        let mut lines = vec![];
+        for text in self.text.lines() {
+            let mut line = Line::new(0);
+            for word in text.split_whitespace() {
+                let mut rest = word;
+                while !rest.is_empty() {
+                    let width = unicode::text_width(rest);
+                    let available = self.available(line.indent);
+                    let needed = if line.words.is_empty() { width } else { line.width() + 1 + width };
+                    if needed <= available {
+                        line.words.push((rest, width));
+                        break;
+                    }
+                    if line.words.is_empty() {
+                        // too long for any line, take as much as fits
+                        let (head, head_width) = split_at_width(rest, available);
+                        line.words.push((head, head_width));
+                        rest = &rest[head.len()..];
+                        if rest.is_empty() {
+                            // a wide character on a line narrower than it
+                            break;
+                        }
+                    }
+                    lines.push(line);
+                    line = Line::new(self.hanging_indent);
+                }
+            }
+            line.last = true;
+            lines.push(line);
+        }
+        lines
+    }
+
+    fn layout(&self, line: &Line<'a>) -> Vec<(usize, &'a str)> {
+        let slack = self.available(line.indent).saturating_sub(line.width());
+        let gaps = line.words.len().saturating_sub(1);
+        let (mut column, justify) = match self.alignment {
+            Alignment::Left => (line.indent, false),
+            Alignment::Center => (line.indent + slack / 2, false),
+            Alignment::Right => (line.indent + slack, false),
+            Alignment::Justify => (line.indent, !line.last && gaps > 0),
+        };
+
+        let mut words = vec![];
+        for (i, &(word, width)) in line.words.iter().enumerate() {
+            words.push((column, word));
+            column += width + 1;
+            if justify && i < gaps {
+                column += slack / gaps + if i < slack % gaps { 1 } else { 0 };
+            }
+        }
+        words
+    }
+}
+
+/// Splits off the longest start of `s` that fits into `width` columns, taking at
+/// least one grapheme.
+fn split_at_width(s: &str, width: usize) -> (&str, usize) {
+    let mut end = 0;
+    let mut taken = 0;
+    for (grapheme, w) in unicode::graphemes(s) {
+        if end > 0 && taken + w > width {
+            break;
+        }
+        end += grapheme.len();
+        taken += w;
+    }
+    (&s[..end], taken)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Alignment, Paragraph};
+
+    #[test]
+    fn wraps_wide_characters_by_columns() {
+        let paragraph = Paragraph::new("日本語 テキスト", 6);
+        assert_eq!(paragraph.wrap(), vec!["日本語", "テキス", "ト"]);
+
+        // a wide character is never split, even where only one column is left
+        let paragraph = Paragraph::new("a 日本", 4);
+        assert_eq!(paragraph.wrap(), vec!["a", "日本"]);
+        let paragraph = Paragraph::new("日本", 3);
+        assert_eq!(paragraph.wrap(), vec!["日", "本"]);
+    }
+
+    #[test]
+    fn keeps_empty_lines() {
+        let paragraph = Paragraph::new("one\n\ntwo\n", 10);
+        assert_eq!(paragraph.wrap(), vec!["one", "", "two"]);
+        assert_eq!(Paragraph::new("", 10).line_count(), 0);
+        assert_eq!(Paragraph::new("   ", 10).wrap(), vec![""]);
+    }
+
+    #[test]
+    fn zero_width_takes_a_grapheme_per_line() {
+        let mut paragraph = Paragraph::new("ab c", 0);
+        assert_eq!(paragraph.wrap(), vec!["a", "b", "c"]);
+        paragraph.hanging_indent = 2;
+        paragraph.alignment = Alignment::Right;
+        assert_eq!(paragraph.wrap(), vec!["a", "  b", "  c"]);
+        // wide characters are taken whole
+        assert_eq!(Paragraph::new("日本", 1).wrap(), vec!["日", "本"]);
+    }
+
+    #[test]
+    fn justifies_all_but_the_last_line() {
+        let mut paragraph = Paragraph::new("a bb c dd e", 7);
+        paragraph.alignment = Alignment::Justify;
+        assert_eq!(paragraph.wrap(), vec!["a  bb c", "dd e"]);
+        paragraph.alignment = Alignment::Center;
+        assert_eq!(paragraph.wrap(), vec!["a bb c", " dd e"]);
+    }
+}
@@ -0,0 +1,113 @@
+//! Display width of text in terminal columns.
+
+use std::borrow::Cow;
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::{Graphemes, UnicodeSegmentation};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Number of columns `s` takes up when printed.
+pub fn text_width(s: &str) -> usize {
+    graphemes(s).map(|(_, width)| width).sum()
+}
+
+/// Number of columns a single character takes up. Control characters are printed
+/// as they are and count as one column.
+pub fn char_width(c: char) -> usize {
+    c.width().unwrap_or(1)
+}
+
+/// Number of columns a grapheme cluster takes up, zero for combining marks
+/// without a base character.
+pub fn grapheme_width(grapheme: &str) -> usize {
+    match grapheme.chars().next() {
+        Some(c) if c.width().is_none() => 1,
+        Some(_) => grapheme.width(),
+        None => 0,
+    }
+}
+
+/// What a cell holds for a grapheme cluster: its first character and the
+/// combining marks that follow it once the cluster is composed with NFC. The
+/// marks are empty whenever Unicode has a precomposed form for the cluster.
+pub fn cell_content(grapheme: &str) -> (char, String) {
+    let mut chars = grapheme.chars();
+    match (chars.next(), chars.next()) {
+        (None, _) => (' ', String::new()),
+        (Some(c), None) => (c, String::new()),
+        _ => {
+            let mut composed = grapheme.nfc();
+            // `unwrap` is safe here, NFC of a non-empty string isn't empty
+            let base = composed.next().unwrap();
+            (base, composed.collect())
+        },
+    }
+}
+
+/// Iterator over the grapheme clusters of a string with their display width.
+pub struct WidthGraphemes<'a> {
+    graphemes: Graphemes<'a>,
+}
+
+impl<'a> Iterator for WidthGraphemes<'a> {
+    type Item = (&'a str, usize);
+
+    fn next(&mut self) -> Option<(&'a str, usize)> {
+        self.graphemes.next().map(|g| (g, grapheme_width(g)))
+    }
+}
+
+/// The grapheme clusters of `s` with the number of columns each takes up.
+pub fn graphemes(s: &str) -> WidthGraphemes<'_> {
+    WidthGraphemes { graphemes: s.graphemes(true) }
+}
+
+/// Shortens `s` to at most `max_width` columns. When something had to be cut off
+/// and there is an `ellipsis`, it replaces the end of the text.
+pub fn truncate<'a>(s: &'a str, max_width: usize, ellipsis: Option<&str>) -> Cow<'a, str> {
+    if text_width(s) <= max_width {
+        return Cow::Borrowed(s);
+    }
+    let ellipsis = match ellipsis {
+        Some(e) => truncate(e, max_width, None).into_owned(),
+        None => String::new(),
+    };
+    let available = max_width - text_width(&ellipsis);
+    let mut width = 0;
+    let mut end = 0;
+    for (grapheme, w) in graphemes(s) {
+        if width + w > available {
+            break;
+        }
+        width += w;
+        end += grapheme.len();
+    }
+    Cow::Owned(format!("{}{}", &s[..end], ellipsis))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cell_content;
+    use backend::HeadlessBackend;
+    use std::default::Default;
+    use {Color, RustBox, RB_NORMAL};
+
+    #[test]
+    fn combining_marks_are_composed() {
+        assert_eq!(cell_content("e\u{301}"), ('é', String::new()));
+        assert_eq!(cell_content("\u{1e31}"), ('\u{1e31}', String::new()));
+        assert_eq!(cell_content("k\u{301}"), ('\u{1e31}', String::new()));
+        // the first mark composes, the second one has nothing left to compose with
+        assert_eq!(cell_content("e\u{301}\u{302}"), ('é', "\u{302}".to_string()));
+        // no precomposed form, the mark is kept
+        assert_eq!(cell_content("x\u{301}"), ('x', "\u{301}".to_string()));
+
+        let backend = HeadlessBackend::new(6, 1);
+        let screen = backend.screen();
+        let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+        rb.print(0, 0, RB_NORMAL, Color::Default, Color::Default, "cafe\u{301}x\u{301}y");
+        rb.present();
+        assert_eq!(screen.line(0).unwrap(), "caféx\u{301}y");
+        assert_eq!(screen.cell(4, 0).unwrap().ch, 'x');
+    }
+}
@@ -0,0 +1,69 @@
+use super::super::{Border, Rect, Surface};
+use unicode;
+
+use super::{CellStyle, Widget};
+
+/// A frame with an optional title, to put other widgets in.
+///
+/// ```
+/// use rustbox::RustBox;
+/// use rustbox::backend::HeadlessBackend;
+/// use rustbox::widgets::{Block, Label, Widget};
+/// use std::default::Default;
+///
+/// let backend = HeadlessBackend::new(12, 3);
+/// let screen = backend.screen();
+/// let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+///
+/// let mut block = Block::titled("Log");
+/// block.render(&rb.surface());
+/// Label::new("all good").render(&block.inner(&rb.surface()));
+/// rb.present();
+///
+/// assert_eq!(screen.lines(), vec!["┌ Log ─────┐", "│all good  │", "└──────────┘"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct Block {
+    pub title: Option<String>,
+    pub border: Border,
+    pub look: CellStyle,
+}
+
+impl Block {
+    pub fn new() -> Block {
+        Block {
+            title: None,
+            border: Border::Single,
+            look: CellStyle::default(),
+        }
+    }
+
+    pub fn titled<S: Into<String>>(title: S) -> Block {
+        Block { title: Some(title.into()), ..Block::new() }
+    }
+
+    /// The part of `surface` inside the border.
+    pub fn inner<'a>(&self, surface: &Surface<'a>) -> Surface<'a> {
+        surface.sub_rect(Rect::new(0, 0, surface.width(), surface.height()).inner())
+    }
+}
+
+impl Default for Block {
+    fn default() -> Self {
+        Block::new()
+    }
+}
+
+impl Widget for Block {
+    fn render(&mut self, surface: &Surface) {
+        let look = self.look;
+        let rect = Rect::new(0, 0, surface.width(), surface.height());
+        surface.draw_box(rect, look.style, look.fg, look.bg, self.border,
+                         self.title.as_ref().map(|title| &title[..]));
+    }
+
+    fn desired_size(&self) -> (usize, usize) {
+        let title = self.title.as_ref().map(|title| unicode::text_width(title) + 2).unwrap_or(0);
+        (title + 2, 2)
+    }
+}
@@ -0,0 +1,177 @@
+use super::super::{Border, Event, Key, Mouse, Paragraph, Rect, Surface};
+use unicode;
+
+use super::{key_of, mouse_in, CellStyle, Response, Widget};
+
+/// Width the text of a dialog is wrapped to.
+const TEXT_WIDTH: usize = 50;
+
+/// A framed message with a row of buttons, e.g. to confirm an action.
+///
+/// Left, Right and Tab pick a button, Enter, Space, a click or the first
+/// letter of a button presses it and Esc cancels. Either way the dialog
+/// reports `Response::Activated`, after which `choice` tells the button
+/// pressed, `None` when cancelled. Dialogs are usually shown on an `Overlays`
+/// stack.
+///
+/// ```
+/// use rustbox::{Event, ExtendedKey, Key, Modifiers};
+/// use rustbox::widgets::{Dialog, Response, Widget};
+///
+/// let mut dialog = Dialog::confirm("Quit", "Discard the unsaved changes?");
+/// let n = Event::KeyEvent(ExtendedKey::new(Key::Char('n'), Modifiers::new()));
+/// assert_eq!(dialog.handle_event(&n), Response::Activated);
+/// assert_eq!(dialog.choice(), Some(1));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Dialog {
+    pub title: Option<String>,
+    pub text: String,
+    pub buttons: Vec<String>,
+    pub border: Border,
+    pub look: CellStyle,
+    pub selected_look: CellStyle,
+    selected: usize,
+    choice: Option<usize>,
+    // Buttons of the last render, in local coordinates
+    button_areas: Vec<Rect>,
+    area: Rect,
+}
+
+impl Dialog {
+    pub fn new<S: Into<String>>(title: Option<String>, text: S, buttons: Vec<String>) -> Dialog {
+        let look = CellStyle::default();
+        Dialog {
+            title,
+            text: text.into(),
+            buttons,
+            border: Border::Single,
+            look,
+            selected_look: look.reversed(),
+            selected: 0,
+            choice: None,
+            button_areas: vec![],
+            area: Rect::default(),
+        }
+    }
+
+    /// A message with an OK button.
+    pub fn message<S: Into<String>, T: Into<String>>(title: S, text: T) -> Dialog {
+        Dialog::new(Some(title.into()), text, vec!["OK".into()])
+    }
+
+    /// A question with Yes and No buttons, Yes being button 0.
+    pub fn confirm<S: Into<String>, T: Into<String>>(title: S, text: T) -> Dialog {
+        Dialog::new(Some(title.into()), text, vec!["Yes".into(), "No".into()])
+    }
+
+    /// The button with the focus.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.buttons.len().saturating_sub(1));
+    }
+
+    /// The button pressed, `None` before that or when cancelled.
+    pub fn choice(&self) -> Option<usize> {
+        self.choice
+    }
+
+    fn press(&mut self, index: Option<usize>) -> Response {
+        self.choice = index;
+        if let Some(index) = index {
+            self.selected = index;
+        }
+        Response::Activated
+    }
+
+    fn button_label(button: &str) -> String {
+        format!("[ {} ]", button)
+    }
+
+    /// Width of the button row, with two columns between buttons.
+    fn buttons_width(&self) -> usize {
+        let width: usize = self.buttons.iter()
+            .map(|button| unicode::text_width(&Dialog::button_label(button)))
+            .sum();
+        width + 2 * self.buttons.len().saturating_sub(1)
+    }
+
+    fn handle_key(&mut self, event: &Event) -> Response {
+        let key = match key_of(event) {
+            Some(key) => key,
+            None => return Response::Ignored,
+        };
+        let last = self.buttons.len().saturating_sub(1);
+        match key.key {
+            Key::Left | Key::BackTab => self.selected = self.selected.saturating_sub(1),
+            Key::Right | Key::Tab => self.selected = (self.selected + 1).min(last),
+            Key::Enter | Key::Char(' ') if !self.buttons.is_empty() => {
+                let selected = self.selected;
+                return self.press(Some(selected));
+            },
+            Key::Esc => return self.press(None),
+            Key::Char(c) if !key.modifiers.ctrl && !key.modifiers.alt => {
+                let c = c.to_lowercase().collect::<String>();
+                let index = self.buttons.iter().position(|button| button.to_lowercase().starts_with(&c));
+                return match index {
+                    Some(index) => self.press(Some(index)),
+                    None => Response::Ignored,
+                };
+            },
+            _ => return Response::Ignored,
+        }
+        Response::Handled
+    }
+}
+
+impl Widget for Dialog {
+    fn render(&mut self, surface: &Surface) {
+        self.area = surface.area();
+        let look = self.look;
+        let (width, height) = (surface.width(), surface.height());
+        surface.draw_box(Rect::new(0, 0, width, height), look.style, look.fg, look.bg, self.border,
+                         self.title.as_ref().map(|title| &title[..]));
+
+        let text = surface.sub(2, 1, width.saturating_sub(4), height.saturating_sub(4));
+        text.print_paragraph(0, 0, look.style, look.fg, look.bg, &Paragraph::new(&self.text, text.width()));
+
+        self.button_areas.clear();
+        let y = height.saturating_sub(2);
+        let mut x = width.saturating_sub(self.buttons_width()) / 2;
+        for (i, button) in self.buttons.iter().enumerate() {
+            let label = Dialog::button_label(button);
+            let look = if i == self.selected { self.selected_look } else { self.look };
+            let written = surface.print(x, y, look.style, look.fg, look.bg, &label);
+            self.button_areas.push(Rect::new(x, y, written, 1));
+            x += written + 2;
+        }
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Response {
+        if key_of(event).is_some() {
+            return self.handle_key(event);
+        }
+        match mouse_in(event, self.area) {
+            Some((Mouse::Left, x, y)) => {
+                match self.button_areas.iter().position(|area| area.contains(x, y)) {
+                    Some(index) => self.press(Some(index)),
+                    None => Response::Handled,
+                }
+            },
+            Some(_) => Response::Handled,
+            None => Response::Ignored,
+        }
+    }
+
+    fn desired_size(&self) -> (usize, usize) {
+        let lines = Paragraph::new(&self.text, TEXT_WIDTH).wrap();
+        let text = lines.iter().map(|line| unicode::text_width(line)).max().unwrap_or(0);
+        let title = self.title.as_ref().map(|title| unicode::text_width(title) + 2).unwrap_or(0);
+        let width = text.max(self.buttons_width()).max(title);
+        // border and padding around the text, a blank line and the buttons
+        (width + 4, lines.len() + 4)
+    }
+}
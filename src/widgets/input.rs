@@ -0,0 +1,309 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::super::{Event, ExtendedKey, Key, Rect, Surface};
+use unicode;
+
+use super::{key_of, CellStyle, Response, Widget};
+
+/// A single line of editable text.
+///
+/// Besides the arrow keys, Home, End, Backspace and Delete it knows the usual
+/// readline bindings:
+///
+/// | Key            | Action                                         |
+/// |----------------|------------------------------------------------|
+/// | Ctrl+A, Ctrl+E | start, end of line                             |
+/// | Ctrl+B, Ctrl+F | one character left, right                      |
+/// | Alt+B, Alt+F   | one word left, right, also Ctrl+Left and Right |
+/// | Ctrl+D         | delete the character under the cursor          |
+/// | Ctrl+H         | delete the character before the cursor         |
+/// | Ctrl+K         | delete to the end of line                      |
+/// | Ctrl+U         | delete to the start of line                    |
+/// | Ctrl+W         | delete the word before the cursor              |
+/// | Alt+D          | delete the word after the cursor               |
+/// | Ctrl+Y         | paste the text deleted last                    |
+/// | Insert         | toggle overwrite mode                          |
+///
+/// ```
+/// use rustbox::{Event, ExtendedKey, Key, Modifiers, RustBox};
+/// use rustbox::backend::HeadlessBackend;
+/// use rustbox::widgets::{TextInput, Widget};
+/// use std::default::Default;
+///
+/// let backend = HeadlessBackend::new(10, 1);
+/// let screen = backend.screen();
+/// let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+///
+/// let mut input = TextInput::new();
+/// input.mask = Some('*');
+/// for c in "secret".chars() {
+///     input.handle_event(&Event::KeyEvent(ExtendedKey::new(Key::Char(c), Modifiers::new())));
+/// }
+/// input.render(&rb.surface());
+/// rb.present();
+///
+/// assert_eq!(input.value(), "secret");
+/// assert_eq!(screen.line(0).unwrap(), "******    ");
+/// assert_eq!(screen.cursor(), Some((6, 0)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct TextInput {
+    value: String,
+    // Byte offset of the cursor, always on a grapheme boundary
+    cursor: usize,
+    // Columns scrolled off to the left
+    scroll: usize,
+    overwrite: bool,
+    // Text deleted last by a kill command, for Ctrl+Y
+    killed: String,
+    /// Only a focused input shows the terminal cursor.
+    pub focused: bool,
+    /// Shown in place of every character, for passwords.
+    pub mask: Option<char>,
+    pub look: CellStyle,
+    area: Rect,
+}
+
+impl TextInput {
+    pub fn new() -> TextInput {
+        TextInput {
+            value: String::new(),
+            cursor: 0,
+            scroll: 0,
+            overwrite: false,
+            killed: String::new(),
+            focused: true,
+            mask: None,
+            look: CellStyle::default(),
+            area: Rect::default(),
+        }
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Replaces the text, moving the cursor to its end.
+    pub fn set_value<S: Into<String>>(&mut self, value: S) {
+        self.value = value.into();
+        self.cursor = self.value.len();
+    }
+
+    /// Byte offset of the cursor in the value.
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Moves the cursor to the grapheme boundary at or before byte offset `cursor`.
+    pub fn set_cursor(&mut self, cursor: usize) {
+        let cursor = cursor.min(self.value.len());
+        self.cursor = if cursor == self.value.len() {
+            cursor
+        } else {
+            self.value.grapheme_indices(true)
+                .map(|(i, _)| i)
+                .take_while(|&i| i <= cursor)
+                .last()
+                .unwrap_or(0)
+        };
+    }
+
+    /// Whether typing replaces the character under the cursor instead of
+    /// inserting before it.
+    pub fn overwrite(&self) -> bool {
+        self.overwrite
+    }
+
+    pub fn set_overwrite(&mut self, overwrite: bool) {
+        self.overwrite = overwrite;
+    }
+
+    fn previous_boundary(&self) -> usize {
+        self.value[..self.cursor].grapheme_indices(true).next_back().map(|(i, _)| i).unwrap_or(0)
+    }
+
+    fn next_boundary(&self) -> usize {
+        self.value[self.cursor..].graphemes(true).next()
+            .map(|grapheme| self.cursor + grapheme.len())
+            .unwrap_or(self.cursor)
+    }
+
+    /// Start of the word before the cursor, skipping whitespace first.
+    fn previous_word(&self) -> usize {
+        let mut start = self.cursor;
+        let mut in_word = false;
+        for (i, grapheme) in self.value[..self.cursor].grapheme_indices(true).rev() {
+            let space = is_space(grapheme);
+            if in_word && space {
+                break;
+            }
+            in_word |= !space;
+            start = i;
+        }
+        start
+    }
+
+    /// End of the word after the cursor, skipping whitespace first.
+    fn next_word(&self) -> usize {
+        let mut end = self.cursor;
+        let mut in_word = false;
+        for grapheme in self.value[self.cursor..].graphemes(true) {
+            let space = is_space(grapheme);
+            if in_word && space {
+                break;
+            }
+            in_word |= !space;
+            end += grapheme.len();
+        }
+        end
+    }
+
+    fn insert(&mut self, c: char) {
+        if self.overwrite {
+            let end = self.next_boundary();
+            self.value.drain(self.cursor..end);
+        }
+        self.value.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    /// Deletes the text between the cursor and `to`, keeping it for Ctrl+Y.
+    fn kill(&mut self, to: usize) {
+        let (start, end) = if to < self.cursor { (to, self.cursor) } else { (self.cursor, to) };
+        self.killed = self.value.drain(start..end).collect();
+        self.cursor = start;
+    }
+
+    fn delete(&mut self, to: usize) {
+        let (start, end) = if to < self.cursor { (to, self.cursor) } else { (self.cursor, to) };
+        self.value.drain(start..end);
+        self.cursor = start;
+    }
+
+    fn yank(&mut self) {
+        self.value.insert_str(self.cursor, &self.killed);
+        self.cursor += self.killed.len();
+    }
+
+    /// Applies a key with Ctrl or Alt held, returning false for unbound ones.
+    fn edit_with_modifier(&mut self, key: &ExtendedKey) -> bool {
+        let (ctrl, alt) = (key.modifiers.ctrl, key.modifiers.alt);
+        match key.key {
+            Key::Char('a') if ctrl => self.cursor = 0,
+            Key::Char('e') if ctrl => self.cursor = self.value.len(),
+            Key::Char('b') if ctrl => self.cursor = self.previous_boundary(),
+            Key::Char('f') if ctrl => self.cursor = self.next_boundary(),
+            Key::Char('d') if ctrl => {
+                let end = self.next_boundary();
+                self.delete(end);
+            },
+            Key::Char('h') if ctrl => {
+                let start = self.previous_boundary();
+                self.delete(start);
+            },
+            Key::Char('k') if ctrl => {
+                let end = self.value.len();
+                self.kill(end);
+            },
+            Key::Char('u') if ctrl => self.kill(0),
+            Key::Char('w') if ctrl => {
+                let start = self.previous_word();
+                self.kill(start);
+            },
+            Key::Char('y') if ctrl => self.yank(),
+            Key::Char('b') if alt => self.cursor = self.previous_word(),
+            Key::Char('f') if alt => self.cursor = self.next_word(),
+            Key::Char('d') if alt => {
+                let end = self.next_word();
+                self.kill(end);
+            },
+            Key::Left => self.cursor = self.previous_word(),
+            Key::Right => self.cursor = self.next_word(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// The graphemes as shown, masked if there is a `mask`.
+    fn shown(&self) -> Vec<(String, usize)> {
+        unicode::graphemes(&self.value).map(|(grapheme, width)| match self.mask {
+            Some(mask) => (mask.to_string(), unicode::char_width(mask)),
+            None => (grapheme.to_string(), width),
+        }).collect()
+    }
+}
+
+impl Default for TextInput {
+    fn default() -> Self {
+        TextInput::new()
+    }
+}
+
+fn is_space(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+impl Widget for TextInput {
+    fn render(&mut self, surface: &Surface) {
+        self.area = surface.area();
+        let look = self.look;
+        let width = surface.width();
+        surface.fill(look.style, look.fg, look.bg, ' ');
+
+        let shown = self.shown();
+        let before = self.value[..self.cursor].graphemes(true).count();
+        let column: usize = shown.iter().take(before).map(|&(_, w)| w).sum();
+
+        // keep the cursor column in view, the last column being left for it at the end
+        if column < self.scroll {
+            self.scroll = column;
+        } else if width > 0 && column >= self.scroll + width {
+            self.scroll = column + 1 - width;
+        }
+
+        let mut x = 0;
+        for &(ref grapheme, w) in shown.iter() {
+            if x >= self.scroll && x + w <= self.scroll + width {
+                surface.print(x - self.scroll, 0, look.style, look.fg, look.bg, grapheme);
+            }
+            x += w;
+        }
+        if self.focused {
+            surface.set_cursor((column - self.scroll) as isize, 0);
+        }
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Response {
+        let key = match key_of(event) {
+            Some(key) => key,
+            None => return Response::Ignored,
+        };
+        if key.modifiers.ctrl || key.modifiers.alt {
+            return if self.edit_with_modifier(&key) { Response::Handled } else { Response::Ignored };
+        }
+        match key.key {
+            Key::Char(c) => self.insert(c),
+            Key::Backspace => {
+                let start = self.previous_boundary();
+                self.delete(start);
+            },
+            Key::Delete => {
+                let end = self.next_boundary();
+                self.delete(end);
+            },
+            Key::Left => self.cursor = self.previous_boundary(),
+            Key::Right => self.cursor = self.next_boundary(),
+            Key::Home => self.cursor = 0,
+            Key::End => self.cursor = self.value.len(),
+            Key::Insert => self.overwrite = !self.overwrite,
+            Key::Enter => return Response::Activated,
+            _ => return Response::Ignored,
+        }
+        Response::Handled
+    }
+
+    fn desired_size(&self) -> (usize, usize) {
+        let width: usize = self.shown().iter().map(|&(_, w)| w).sum();
+        (width + 1, 1)
+    }
+}
@@ -0,0 +1,53 @@
+use super::super::{Alignment, Paragraph, Surface};
+use unicode;
+
+use super::{CellStyle, Widget};
+
+/// One or more lines of text.
+#[derive(Clone, Debug)]
+pub struct Label {
+    pub text: String,
+    pub look: CellStyle,
+    pub alignment: Alignment,
+    /// Word-wraps the text to the width of the label instead of cutting lines off.
+    pub wrap: bool,
+}
+
+impl Label {
+    pub fn new<S: Into<String>>(text: S) -> Label {
+        Label {
+            text: text.into(),
+            look: CellStyle::default(),
+            alignment: Alignment::Left,
+            wrap: false,
+        }
+    }
+}
+
+impl Widget for Label {
+    fn render(&mut self, surface: &Surface) {
+        let look = self.look;
+        if self.wrap {
+            let mut paragraph = Paragraph::new(&self.text, surface.width());
+            paragraph.alignment = self.alignment;
+            surface.print_paragraph(0, 0, look.style, look.fg, look.bg, &paragraph);
+            return;
+        }
+
+        for (y, line) in self.text.lines().enumerate() {
+            let line = unicode::truncate(line, surface.width(), Some("…"));
+            let slack = surface.width() - unicode::text_width(&line);
+            let x = match self.alignment {
+                Alignment::Center => slack / 2,
+                Alignment::Right => slack,
+                Alignment::Left | Alignment::Justify => 0,
+            };
+            surface.print(x, y, look.style, look.fg, look.bg, &line);
+        }
+    }
+
+    fn desired_size(&self) -> (usize, usize) {
+        let width = self.text.lines().map(unicode::text_width).max().unwrap_or(0);
+        (width, self.text.lines().count())
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::BTreeSet;
+
+use super::super::{Event, Key, Mouse, Rect, Surface};
+use unicode;
+
+use super::selection::Selection;
+use super::{key_of, mouse_in, CellStyle, Response, Widget};
+
+/// Columns taken by the check box in front of items in multi-selection mode.
+const CHECK_BOX_WIDTH: usize = 4;
+
+/// A scrollable list of items, one of which can be selected.
+///
+/// Typing narrows the list down to the items containing the typed text,
+/// Backspace takes back a character and Esc clears the filter. With
+/// `multi_select` every item gets a check box, toggled with Space or a click
+/// on it, and Ctrl+A checks all items shown.
+///
+/// ```
+/// use rustbox::{Event, ExtendedKey, Key, Modifiers};
+/// use rustbox::widgets::{List, Widget};
+///
+/// let mut list = List::new(vec!["apple".into(), "banana".into(), "cherry".into()]);
+/// for c in "an".chars() {
+///     list.handle_event(&Event::KeyEvent(ExtendedKey::new(Key::Char(c), Modifiers::new())));
+/// }
+/// assert_eq!(list.visible_items(), &[1]);
+/// assert_eq!(list.selected(), Some(1));
+/// ```
+#[derive(Clone, Debug)]
+pub struct List {
+    items: Vec<String>,
+    pub look: CellStyle,
+    pub selected_look: CellStyle,
+    pub filter_look: CellStyle,
+    /// Shows check boxes to pick several items.
+    pub multi_select: bool,
+    /// Lets typing filter the items.
+    pub filterable: bool,
+    checked: BTreeSet<usize>,
+    filter: String,
+    // Indices of the items matching the filter, the selection being an index into these
+    visible: Vec<usize>,
+    selection: Selection,
+    area: Rect,
+}
+
+impl List {
+    pub fn new(items: Vec<String>) -> List {
+        let look = CellStyle::default();
+        let mut list = List {
+            items,
+            look,
+            selected_look: look.reversed(),
+            filter_look: look,
+            multi_select: false,
+            filterable: true,
+            checked: BTreeSet::new(),
+            filter: String::new(),
+            visible: vec![],
+            selection: Selection::default(),
+            area: Rect::default(),
+        };
+        list.refilter();
+        list
+    }
+
+    pub fn items(&self) -> &[String] {
+        &self.items
+    }
+
+    /// Replaces the items, keeping the selection where it still fits.
+    pub fn set_items(&mut self, items: Vec<String>) {
+        self.items = items;
+        let len = self.items.len();
+        self.checked = self.checked.iter().cloned().filter(|&i| i < len).collect();
+        self.refilter();
+    }
+
+    /// Index of the selected item.
+    pub fn selected(&self) -> Option<usize> {
+        self.selection.selected.and_then(|row| self.visible.get(row).cloned())
+    }
+
+    /// Selects the item at `index`, clearing the filter if it hides the item.
+    pub fn select(&mut self, index: Option<usize>) {
+        let row = match index {
+            Some(index) if index < self.items.len() => {
+                if !self.visible.contains(&index) {
+                    self.set_filter("");
+                }
+                self.visible.iter().position(|&i| i == index)
+            },
+            _ => None,
+        };
+        self.selection.select(row, self.visible.len());
+    }
+
+    /// Index of the first visible item.
+    pub fn offset(&self) -> usize {
+        self.visible.get(self.selection.offset).cloned().unwrap_or(0)
+    }
+
+    /// Indices of the items matching the filter.
+    pub fn visible_items(&self) -> &[usize] {
+        &self.visible
+    }
+
+    pub fn filter(&self) -> &str {
+        &self.filter
+    }
+
+    /// Shows only the items containing `filter`, ignoring case.
+    pub fn set_filter(&mut self, filter: &str) {
+        self.filter = filter.to_string();
+        self.refilter();
+    }
+
+    /// Indices of the checked items, in order.
+    pub fn checked(&self) -> Vec<usize> {
+        self.checked.iter().cloned().collect()
+    }
+
+    pub fn is_checked(&self, index: usize) -> bool {
+        self.checked.contains(&index)
+    }
+
+    pub fn set_checked(&mut self, index: usize, checked: bool) {
+        if checked && index < self.items.len() {
+            self.checked.insert(index);
+        } else {
+            self.checked.remove(&index);
+        }
+    }
+
+    /// Recomputes the visible items, keeping the selected one selected if it
+    /// still matches.
+    fn refilter(&mut self) {
+        let selected = self.selected();
+        let filter = self.filter.to_lowercase();
+        self.visible = self.items.iter().enumerate()
+            .filter(|&(_, item)| item.to_lowercase().contains(&filter))
+            .map(|(i, _)| i)
+            .collect();
+        let row = selected.and_then(|index| self.visible.iter().position(|&i| i == index));
+        let row = if row.is_none() && !self.visible.is_empty() { Some(0) } else { row };
+        self.selection.select(row, self.visible.len());
+    }
+
+    fn toggle(&mut self, row: usize) {
+        if let Some(&index) = self.visible.get(row) {
+            let checked = !self.is_checked(index);
+            self.set_checked(index, checked);
+        }
+    }
+
+    /// Rows the items are shown in, one less while the filter line is shown.
+    fn list_height(&self) -> usize {
+        if self.filter.is_empty() {
+            self.area.height
+        } else {
+            self.area.height.saturating_sub(1)
+        }
+    }
+
+    fn handle_key(&mut self, event: &Event) -> Response {
+        let key = match key_of(event) {
+            Some(key) => key,
+            None => return Response::Ignored,
+        };
+        let (ctrl, alt) = (key.modifiers.ctrl, key.modifiers.alt);
+        match key.key {
+            Key::Char(' ') if self.multi_select && !ctrl && !alt => {
+                if let Some(row) = self.selection.selected {
+                    self.toggle(row);
+                }
+            },
+            Key::Char('a') if self.multi_select && ctrl => {
+                for i in self.visible.clone() {
+                    self.checked.insert(i);
+                }
+            },
+            Key::Char(c) if self.filterable && !ctrl && !alt => {
+                self.filter.push(c);
+                self.refilter();
+            },
+            Key::Backspace if !self.filter.is_empty() => {
+                self.filter.pop();
+                self.refilter();
+            },
+            Key::Esc if !self.filter.is_empty() => self.set_filter(""),
+            _ => {
+                let page = self.list_height();
+                return self.selection.handle_key(&key, self.visible.len(), page);
+            },
+        }
+        Response::Handled
+    }
+}
+
+impl Widget for List {
+    fn render(&mut self, surface: &Surface) {
+        self.area = surface.area();
+        let height = self.list_height();
+        self.selection.keep_visible(self.visible.len(), height);
+
+        let width = surface.width();
+        let blank = " ".repeat(width);
+        let rows = self.visible.iter().enumerate().skip(self.selection.offset).take(height);
+        for (y, (row, &index)) in rows.enumerate() {
+            let look = if Some(row) == self.selection.selected { self.selected_look } else { self.look };
+            let text = if self.multi_select {
+                let check = if self.is_checked(index) { "[x] " } else { "[ ] " };
+                format!("{}{}", check, self.items[index])
+            } else {
+                self.items[index].clone()
+            };
+            surface.print(0, y, look.style, look.fg, look.bg, &blank);
+            surface.print_truncated(0, y, look.style, look.fg, look.bg, &text, width, Some("…"));
+        }
+
+        if !self.filter.is_empty() && surface.height() > 0 {
+            let look = self.filter_look;
+            let y = surface.height() - 1;
+            surface.print(0, y, look.style, look.fg, look.bg, &blank);
+            surface.print_truncated(0, y, look.style, look.fg, look.bg, &format!("/{}", self.filter),
+                                    width, Some("…"));
+        }
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Response {
+        if key_of(event).is_some() {
+            return self.handle_key(event);
+        }
+        let len = self.visible.len();
+        let height = self.list_height();
+        match mouse_in(event, self.area) {
+            Some((Mouse::Left, x, y)) if y < height && self.selection.offset + y < len => {
+                let row = self.selection.offset + y;
+                self.selection.select(Some(row), len);
+                if self.multi_select && x < CHECK_BOX_WIDTH {
+                    self.toggle(row);
+                }
+                Response::Handled
+            },
+            Some((Mouse::WheelUp, _, _)) => {
+                self.selection.scroll_by(-1, len, height);
+                Response::Handled
+            },
+            Some((Mouse::WheelDown, _, _)) => {
+                self.selection.scroll_by(1, len, height);
+                Response::Handled
+            },
+            _ => Response::Ignored,
+        }
+    }
+
+    fn desired_size(&self) -> (usize, usize) {
+        let check_box = if self.multi_select { CHECK_BOX_WIDTH } else { 0 };
+        let width = self.items.iter().map(|item| unicode::text_width(item)).max().unwrap_or(0);
+        (width + check_box, self.items.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::List;
+    use super::super::Widget;
+    use backend::HeadlessBackend;
+    use std::default::Default;
+    use {Event, ExtendedKey, Key, Modifiers, RustBox};
+
+    fn fruit() -> List {
+        List::new(vec!["apple".into(), "banana".into(), "cherry".into(), "grape".into()])
+    }
+
+    fn press(list: &mut List, key: Key) {
+        list.handle_event(&Event::KeyEvent(ExtendedKey::new(key, Modifiers::new())));
+    }
+
+    #[test]
+    fn selection_follows_the_item_through_filters() {
+        let mut list = fruit();
+        list.select(Some(3));
+        press(&mut list, Key::Char('r'));
+        assert_eq!(list.visible_items(), &[2, 3]);
+        assert_eq!(list.selected(), Some(3));
+        press(&mut list, Key::Char('A'));
+        assert_eq!(list.visible_items(), &[3]);
+        assert_eq!(list.selected(), Some(3));
+        press(&mut list, Key::Backspace);
+        press(&mut list, Key::Backspace);
+        assert_eq!(list.visible_items(), &[0, 1, 2, 3]);
+        assert_eq!(list.selected(), Some(3));
+
+        // once the selected item is filtered out the first one shown is selected
+        list.set_filter("an");
+        assert_eq!(list.selected(), Some(1));
+        // selecting a hidden item clears the filter
+        list.select(Some(0));
+        assert_eq!(list.filter(), "");
+        assert_eq!(list.selected(), Some(0));
+    }
+
+    #[test]
+    fn filter_matching_nothing() {
+        let mut list = fruit();
+        list.select(Some(1));
+        list.set_filter("kiwi");
+        assert!(list.visible_items().is_empty());
+        assert_eq!(list.selected(), None);
+        press(&mut list, Key::Down);
+        press(&mut list, Key::End);
+        assert_eq!(list.selected(), None);
+
+        let backend = HeadlessBackend::new(8, 3);
+        let screen = backend.screen();
+        let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+        list.render(&rb.surface());
+        rb.present();
+        assert_eq!(screen.lines(), vec!["        ", "        ", "/kiwi   "]);
+
+        press(&mut list, Key::Esc);
+        assert_eq!(list.visible_items().len(), 4);
+        assert_eq!(list.selected(), Some(0));
+    }
+}
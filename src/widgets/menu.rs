@@ -0,0 +1,845 @@
+use super::super::{Border, Color, Event, ExtendedKey, Key, Mouse, OutputMode, Rect, Surface};
+use super::super::{RB_BOLD, RB_DIM, RB_NORMAL, RB_UNDERLINE};
+use unicode;
+
+use super::{key_of, mouse_in, CellStyle, Response, Widget};
+
+/// An entry of a menu. An `&` in a label marks the following letter as the
+/// accelerator of the entry and `&&` stands for a literal `&`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MenuItem {
+    Action { label: String, enabled: bool },
+    Submenu { label: String, enabled: bool, items: Vec<MenuItem> },
+    Separator,
+}
+
+impl MenuItem {
+    pub fn action<S: Into<String>>(label: S) -> MenuItem {
+        MenuItem::Action { label: label.into(), enabled: true }
+    }
+
+    pub fn submenu<S: Into<String>>(label: S, items: Vec<MenuItem>) -> MenuItem {
+        MenuItem::Submenu { label: label.into(), enabled: true, items }
+    }
+
+    pub fn separator() -> MenuItem {
+        MenuItem::Separator
+    }
+
+    /// The same entry greyed out, so it can't be chosen.
+    pub fn disabled(self) -> MenuItem {
+        match self {
+            MenuItem::Action { label, .. } => MenuItem::Action { label, enabled: false },
+            MenuItem::Submenu { label, items, .. } => {
+                MenuItem::Submenu { label, enabled: false, items }
+            },
+            MenuItem::Separator => MenuItem::Separator,
+        }
+    }
+
+    /// The label as given, with accelerator markers.
+    pub fn label(&self) -> Option<&str> {
+        match *self {
+            MenuItem::Action { ref label, .. } | MenuItem::Submenu { ref label, .. } => Some(label),
+            MenuItem::Separator => None,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        match *self {
+            MenuItem::Action { enabled, .. } | MenuItem::Submenu { enabled, .. } => enabled,
+            MenuItem::Separator => false,
+        }
+    }
+
+    fn items(&self) -> Option<&[MenuItem]> {
+        match *self {
+            MenuItem::Submenu { ref items, .. } => Some(items),
+            _ => None,
+        }
+    }
+
+    fn accelerator(&self) -> Option<char> {
+        self.label().and_then(|label| parse_label(label).1).map(|(_, c)| c)
+    }
+
+    /// Columns taken by the label as shown.
+    fn width(&self) -> usize {
+        self.label().map(|label| unicode::text_width(&parse_label(label).0)).unwrap_or(0)
+    }
+}
+
+/// The label without markers, and the byte offset in it and lowercase letter
+/// of the accelerator.
+fn parse_label(label: &str) -> (String, Option<(usize, char)>) {
+    let mut shown = String::new();
+    let mut accelerator = None;
+    let mut chars = label.chars();
+    while let Some(c) = chars.next() {
+        if c != '&' {
+            shown.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('&') => shown.push('&'),
+            Some(c) => {
+                if accelerator.is_none() {
+                    accelerator = c.to_lowercase().next().map(|lower| (shown.len(), lower));
+                }
+                shown.push(c);
+            },
+            None => (),
+        }
+    }
+    (shown, accelerator)
+}
+
+/// Prints a label with its accelerator underlined and returns its width.
+fn print_label(surface: &Surface, x: usize, y: usize, label: &str, look: CellStyle) -> usize {
+    let (shown, accelerator) = parse_label(label);
+    let (style, fg, bg) = (look.style, look.fg, look.bg);
+    match accelerator {
+        Some((at, _)) => {
+            let letter = shown[at..].chars().next().map(char::len_utf8).unwrap_or(0);
+            let mut written = surface.print(x, y, style, fg, bg, &shown[..at]);
+            written += surface.print(x + written, y, style | RB_UNDERLINE, fg, bg, &shown[at..at + letter]);
+            written + surface.print(x + written, y, style, fg, bg, &shown[at + letter..])
+        },
+        None => surface.print(x, y, style, fg, bg, &shown),
+    }
+}
+
+/// How menus are drawn.
+#[derive(Clone, Copy)]
+struct Looks {
+    border: Border,
+    look: CellStyle,
+    selected: CellStyle,
+    disabled: CellStyle,
+}
+
+/// An open menu of a cascade.
+struct Level {
+    selected: Option<usize>,
+    // Where the menu was last drawn, in local coordinates
+    area: Rect,
+}
+
+/// What a cascade of menus did with an event.
+#[derive(PartialEq)]
+enum Outcome {
+    Ignored,
+    Handled,
+    /// The entry at the path was chosen.
+    Chosen(Vec<usize>),
+    /// Left or Right was pressed with nothing to close or open.
+    Previous,
+    Next,
+    Closed,
+}
+
+/// Open menus, each one a submenu of the one before.
+struct Cascade {
+    x: usize,
+    y: usize,
+    levels: Vec<Level>,
+}
+
+impl Cascade {
+    /// The menu of `items` at `x`, `y`, with the first entry that can be chosen selected.
+    fn new(items: &[MenuItem], x: usize, y: usize) -> Cascade {
+        Cascade {
+            x,
+            y,
+            levels: vec![Level { selected: next_enabled(items, None, 1), area: Rect::default() }],
+        }
+    }
+
+    /// The entries of the menu at `depth`.
+    fn items<'a>(&self, root: &'a [MenuItem], depth: usize) -> &'a [MenuItem] {
+        let mut items = root;
+        for level in &self.levels[..depth] {
+            items = level.selected
+                .and_then(|i| items.get(i))
+                .and_then(MenuItem::items)
+                .unwrap_or(&[]);
+        }
+        items
+    }
+
+    fn path(&self) -> Vec<usize> {
+        self.levels.iter().filter_map(|level| level.selected).collect()
+    }
+
+    /// Opens the submenu of entry `index` of the top menu, or chooses the entry.
+    fn enter(&mut self, root: &[MenuItem], index: usize) -> Outcome {
+        let depth = self.levels.len() - 1;
+        let item = match self.items(root, depth).get(index) {
+            Some(item) if item.is_enabled() => item,
+            _ => return Outcome::Handled,
+        };
+        self.levels[depth].selected = Some(index);
+        match item.items() {
+            Some(items) => {
+                self.levels.push(Level { selected: next_enabled(items, None, 1), area: Rect::default() });
+                Outcome::Handled
+            },
+            None => Outcome::Chosen(self.path()),
+        }
+    }
+
+    fn handle_key(&mut self, root: &[MenuItem], key: &ExtendedKey) -> Outcome {
+        let depth = self.levels.len() - 1;
+        let items = self.items(root, depth);
+        let selected = self.levels[depth].selected;
+        match key.key {
+            Key::Up => self.levels[depth].selected = next_enabled(items, selected, -1).or(selected),
+            Key::Down => self.levels[depth].selected = next_enabled(items, selected, 1).or(selected),
+            Key::Home => self.levels[depth].selected = next_enabled(items, None, 1),
+            Key::End => self.levels[depth].selected = next_enabled(items, None, -1),
+            Key::Right => {
+                let submenu = selected.and_then(|i| items.get(i))
+                    .map_or(false, |item| item.is_enabled() && item.items().is_some());
+                return match selected {
+                    Some(index) if submenu => self.enter(root, index),
+                    _ => Outcome::Next,
+                };
+            },
+            Key::Left if depth > 0 => {
+                self.levels.pop();
+            },
+            Key::Left => return Outcome::Previous,
+            Key::Esc if depth > 0 => {
+                self.levels.pop();
+            },
+            Key::Esc => return Outcome::Closed,
+            Key::Enter | Key::Char(' ') => {
+                return match selected {
+                    Some(index) => self.enter(root, index),
+                    None => Outcome::Handled,
+                };
+            },
+            Key::Char(c) if !key.modifiers.ctrl => {
+                let c = c.to_lowercase().next().unwrap_or(c);
+                let index = items.iter().position(|item| item.is_enabled() && item.accelerator() == Some(c));
+                return match index {
+                    Some(index) => self.enter(root, index),
+                    None => Outcome::Handled,
+                };
+            },
+            _ => return Outcome::Ignored,
+        }
+        Outcome::Handled
+    }
+
+    /// Handles a click at local `x`, `y`.
+    fn click(&mut self, root: &[MenuItem], x: usize, y: usize) -> Outcome {
+        let depth = match self.levels.iter().rposition(|level| level.area.contains(x, y)) {
+            Some(depth) => depth,
+            None => return Outcome::Closed,
+        };
+        self.levels.truncate(depth + 1);
+        let area = self.levels[depth].area;
+        if y > area.y && y + 1 < area.bottom() {
+            self.enter(root, y - area.y - 1)
+        } else {
+            Outcome::Handled
+        }
+    }
+
+    fn render(&mut self, surface: &Surface, root: &[MenuItem], looks: Looks) {
+        let (width, height) = (surface.width(), surface.height());
+        for depth in 0..self.levels.len() {
+            let items = self.items(root, depth);
+            let arrow = if items.iter().any(|item| item.items().is_some()) { 2 } else { 0 };
+            let w = (items.iter().map(MenuItem::width).max().unwrap_or(0) + arrow + 4).min(width);
+            let h = (items.len() + 2).min(height);
+            let (x, y) = if depth == 0 {
+                (self.x.min(width - w), self.y.min(height - h))
+            } else {
+                // next to the selected entry of the parent, or to its left where there's no room
+                let parent = self.levels[depth - 1].area;
+                let row = parent.y + self.levels[depth - 1].selected.unwrap_or(0);
+                let x = if parent.right() + w <= width { parent.right() } else { parent.x.saturating_sub(w) };
+                (x, row.min(height - h))
+            };
+            let area = Rect::new(x, y, w, h);
+            self.levels[depth].area = area;
+            render_menu(surface, area, items, self.levels[depth].selected, looks);
+        }
+    }
+}
+
+fn render_menu(surface: &Surface, area: Rect, items: &[MenuItem], selected: Option<usize>, looks: Looks) {
+    let look = looks.look;
+    surface.draw_box(area, look.style, look.fg, look.bg, looks.border, None);
+    let blank = " ".repeat(area.width.saturating_sub(2));
+    let arrow = if looks.border == Border::Ascii { ">" } else { "▸" };
+    for (i, item) in items.iter().enumerate().take(area.height.saturating_sub(2)) {
+        let y = area.y + 1 + i;
+        let label = match item.label() {
+            Some(label) => label,
+            None => {
+                surface.draw_hline(area.x, y, area.width, look.style, look.fg, look.bg, looks.border);
+                continue;
+            },
+        };
+        let row = match (Some(i) == selected, item.is_enabled()) {
+            (true, _) => looks.selected,
+            (false, true) => look,
+            (false, false) => looks.disabled,
+        };
+        surface.print(area.x + 1, y, row.style, row.fg, row.bg, &blank);
+        print_label(&surface.sub(area.x + 2, y, area.width.saturating_sub(4), 1), 0, 0, label, row);
+        if item.items().is_some() {
+            surface.print(area.right().saturating_sub(3), y, row.style, row.fg, row.bg, arrow);
+        }
+    }
+}
+
+/// The next entry after `from` in direction `step` that can be chosen, from
+/// the start or end without `from`.
+fn next_enabled(items: &[MenuItem], from: Option<usize>, step: isize) -> Option<usize> {
+    let mut index = match from {
+        Some(from) => from as isize + step,
+        None if step > 0 => 0,
+        None => items.len() as isize - 1,
+    };
+    while index >= 0 && (index as usize) < items.len() {
+        if items[index as usize].is_enabled() {
+            return Some(index as usize);
+        }
+        index += step;
+    }
+    None
+}
+
+/// `look` as drawn on `surface`, with a dim look turned into dark gray text
+/// where the terminal can't show dim text.
+fn shown_dim(look: CellStyle, surface: &Surface) -> CellStyle {
+    let rb = surface.rustbox();
+    if !look.style.contains(RB_DIM) || rb.supported_styles().contains(RB_DIM) {
+        return look;
+    }
+    let (gray, bright) = match rb.output_mode() {
+        // bright black
+        OutputMode::Normal | OutputMode::NoOutput => (Color::Black, RB_BOLD),
+        _ => (Color::Byte(8), RB_NORMAL),
+    };
+    CellStyle { style: (look.style & !RB_DIM) | bright, fg: gray, ..look }
+}
+
+/// A menu that pops up where the surface it is rendered into is right-clicked.
+///
+/// Render it into the whole area it serves, after everything else, so the
+/// menu is drawn on top. Up and Down select an entry, Right or Enter open a
+/// submenu, Left or Esc close it again, and Enter, a click or an accelerator
+/// letter choose an entry. Choosing one reports `Response::Activated`, with
+/// its path of indices in `chosen`.
+///
+/// ```
+/// use rustbox::{Event, ExtendedKey, Key, Modifiers, Mouse, RustBox};
+/// use rustbox::backend::HeadlessBackend;
+/// use rustbox::widgets::{ContextMenu, MenuItem, Widget};
+/// use std::default::Default;
+///
+/// let backend = HeadlessBackend::new(30, 10);
+/// let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+///
+/// let mut menu = ContextMenu::new(vec![
+///     MenuItem::action("&Copy"),
+///     MenuItem::action("&Paste").disabled(),
+///     MenuItem::separator(),
+///     MenuItem::submenu("&Sort", vec![MenuItem::action("By &name"), MenuItem::action("By &size")]),
+/// ]);
+/// menu.render(&rb.surface());
+/// menu.handle_event(&Event::MouseEvent(Mouse::Right, 3, 4));
+/// assert!(menu.is_open());
+/// menu.render(&rb.surface());
+///
+/// for c in "ss".chars() {
+///     let key = Event::KeyEvent(ExtendedKey::new(Key::Char(c), Modifiers::new()));
+///     menu.handle_event(&key);
+/// }
+/// assert_eq!(menu.chosen(), Some(&[3, 1][..]));
+/// assert!(!menu.is_open());
+/// ```
+pub struct ContextMenu {
+    pub items: Vec<MenuItem>,
+    pub border: Border,
+    pub look: CellStyle,
+    pub selected_look: CellStyle,
+    pub disabled_look: CellStyle,
+    cascade: Option<Cascade>,
+    chosen: Option<Vec<usize>>,
+    area: Rect,
+}
+
+impl ContextMenu {
+    pub fn new(items: Vec<MenuItem>) -> ContextMenu {
+        let look = CellStyle::default();
+        ContextMenu {
+            items,
+            border: Border::Single,
+            look,
+            selected_look: look.reversed(),
+            disabled_look: CellStyle { style: look.style | RB_DIM, ..look },
+            cascade: None,
+            chosen: None,
+            area: Rect::default(),
+        }
+    }
+
+    /// Opens the menu with its top left corner at local `x`, `y`.
+    pub fn open_at(&mut self, x: usize, y: usize) {
+        self.cascade = Some(Cascade::new(&self.items, x, y));
+    }
+
+    pub fn close(&mut self) {
+        self.cascade = None;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.cascade.is_some()
+    }
+
+    /// Indices of the entry chosen last, from the top menu down.
+    pub fn chosen(&self) -> Option<&[usize]> {
+        self.chosen.as_ref().map(|path| &path[..])
+    }
+
+    fn looks(&self, surface: &Surface) -> Looks {
+        Looks {
+            border: self.border,
+            look: self.look,
+            selected: self.selected_look,
+            disabled: shown_dim(self.disabled_look, surface),
+        }
+    }
+
+    fn finish(&mut self, outcome: Outcome) -> Response {
+        match outcome {
+            Outcome::Ignored => Response::Ignored,
+            Outcome::Chosen(path) => {
+                self.chosen = Some(path);
+                self.close();
+                Response::Activated
+            },
+            Outcome::Closed => {
+                self.close();
+                Response::Handled
+            },
+            Outcome::Handled | Outcome::Previous | Outcome::Next => Response::Handled,
+        }
+    }
+}
+
+impl Widget for ContextMenu {
+    fn render(&mut self, surface: &Surface) {
+        self.area = surface.area();
+        let looks = self.looks(surface);
+        if let Some(ref mut cascade) = self.cascade {
+            cascade.render(surface, &self.items, looks);
+        }
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Response {
+        if let Some((Mouse::Right, x, y)) = mouse_in(event, self.area) {
+            self.open_at(x, y);
+            return Response::Handled;
+        }
+        let outcome = match (self.cascade.as_mut(), key_of(event), mouse_in(event, self.area)) {
+            (None, _, _) => Outcome::Ignored,
+            (Some(cascade), Some(key), _) => cascade.handle_key(&self.items, &key),
+            (Some(cascade), None, Some((Mouse::Left, x, y))) => cascade.click(&self.items, x, y),
+            (Some(_), None, Some((Mouse::Middle, _, _))) => Outcome::Closed,
+            (Some(_), _, _) => Outcome::Handled,
+        };
+        self.finish(outcome)
+    }
+
+    fn desired_size(&self) -> (usize, usize) {
+        let arrow = if self.items.iter().any(|item| item.items().is_some()) { 2 } else { 0 };
+        let width = self.items.iter().map(MenuItem::width).max().unwrap_or(0);
+        (width + arrow + 4, self.items.len() + 2)
+    }
+}
+
+/// A row of menu titles with pull-down menus.
+///
+/// The bar takes the first row of the surface it is rendered into and the
+/// menus open below it, so render it into the whole area it serves, after
+/// everything else. Alt and the accelerator letter of a title or a click on it
+/// open a menu, Left and Right move to the neighbouring one; inside a menu the
+/// keys work like in a `ContextMenu`. Choosing an entry reports
+/// `Response::Activated`, with its path of indices, starting with the index of
+/// the menu, in `chosen`.
+///
+/// ```
+/// use rustbox::{Event, ExtendedKey, Key, Modifiers, RustBox};
+/// use rustbox::backend::HeadlessBackend;
+/// use rustbox::widgets::{MenuBar, MenuItem, Response, Widget};
+/// use std::default::Default;
+///
+/// let backend = HeadlessBackend::new(20, 6);
+/// let screen = backend.screen();
+/// let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+///
+/// let mut bar = MenuBar::new(vec![
+///     MenuItem::submenu("&File", vec![MenuItem::action("&Open"), MenuItem::separator(),
+///                                     MenuItem::action("&Quit")]),
+///     MenuItem::submenu("&Help", vec![MenuItem::action("&About")]),
+/// ]);
+/// let alt_f = Event::KeyEvent(ExtendedKey::new(Key::Char('f'), Modifiers::new_all(false, false, true)));
+/// bar.handle_event(&alt_f);
+/// bar.render(&rb.surface());
+/// rb.present();
+/// assert_eq!(screen.lines(), vec![" File  Help         ",
+///                                 "┌──────┐            ",
+///                                 "│ Open │            ",
+///                                 "├──────┤            ",
+///                                 "│ Quit │            ",
+///                                 "└──────┘            "]);
+///
+/// let q = Event::KeyEvent(ExtendedKey::new(Key::Char('q'), Modifiers::new()));
+/// assert_eq!(bar.handle_event(&q), Response::Activated);
+/// assert_eq!(bar.chosen(), Some(&[0, 2][..]));
+/// ```
+pub struct MenuBar {
+    pub menus: Vec<MenuItem>,
+    pub border: Border,
+    pub look: CellStyle,
+    pub selected_look: CellStyle,
+    pub disabled_look: CellStyle,
+    // The open menu and its cascade
+    open: Option<(usize, Cascade)>,
+    chosen: Option<Vec<usize>>,
+    // Titles of the last render, in local coordinates
+    titles: Vec<Rect>,
+    area: Rect,
+}
+
+impl MenuBar {
+    pub fn new(menus: Vec<MenuItem>) -> MenuBar {
+        let look = CellStyle::default();
+        MenuBar {
+            menus,
+            border: Border::Single,
+            look,
+            selected_look: look.reversed(),
+            disabled_look: CellStyle { style: look.style | RB_DIM, ..look },
+            open: None,
+            chosen: None,
+            titles: vec![],
+            area: Rect::default(),
+        }
+    }
+
+    /// Opens menu `index`, or chooses it when it has no entries of its own.
+    pub fn open(&mut self, index: usize) -> Response {
+        let (enabled, items) = match self.menus.get(index) {
+            Some(menu) => (menu.is_enabled(), menu.items()),
+            None => return Response::Ignored,
+        };
+        match items {
+            _ if !enabled => Response::Handled,
+            Some(items) => {
+                let x = self.title_x(index);
+                self.open = Some((index, Cascade::new(items, x, 0)));
+                Response::Handled
+            },
+            None => {
+                self.open = None;
+                self.chosen = Some(vec![index]);
+                Response::Activated
+            },
+        }
+    }
+
+    pub fn close(&mut self) {
+        self.open = None;
+    }
+
+    /// Index of the open menu.
+    pub fn open_menu(&self) -> Option<usize> {
+        self.open.as_ref().map(|&(index, _)| index)
+    }
+
+    /// Indices of the entry chosen last, from the menu on the bar down.
+    pub fn chosen(&self) -> Option<&[usize]> {
+        self.chosen.as_ref().map(|path| &path[..])
+    }
+
+    fn looks(&self, surface: &Surface) -> Looks {
+        Looks {
+            border: self.border,
+            look: self.look,
+            selected: self.selected_look,
+            disabled: shown_dim(self.disabled_look, surface),
+        }
+    }
+
+    /// Column the title of menu `index` starts at.
+    fn title_x(&self, index: usize) -> usize {
+        self.menus.iter().take(index).map(|menu| menu.width() + 2).sum()
+    }
+
+    /// Opens the next menu that can be opened from `index` in direction `step`.
+    fn open_next(&mut self, index: usize, step: isize) -> Response {
+        let len = self.menus.len() as isize;
+        let mut next = index as isize;
+        for _ in 0..len {
+            next = (next + step + len) % len;
+            if self.menus[next as usize].items().is_some() && self.menus[next as usize].is_enabled() {
+                return self.open(next as usize);
+            }
+        }
+        Response::Handled
+    }
+
+    fn finish(&mut self, index: usize, outcome: Outcome) -> Response {
+        match outcome {
+            Outcome::Ignored => Response::Ignored,
+            Outcome::Handled => Response::Handled,
+            Outcome::Chosen(path) => {
+                let mut chosen = vec![index];
+                chosen.extend(path);
+                self.chosen = Some(chosen);
+                self.close();
+                Response::Activated
+            },
+            Outcome::Previous => self.open_next(index, -1),
+            Outcome::Next => self.open_next(index, 1),
+            Outcome::Closed => {
+                self.close();
+                Response::Handled
+            },
+        }
+    }
+
+    fn handle_key(&mut self, key: &ExtendedKey) -> Response {
+        if key.modifiers.alt {
+            if let Key::Char(c) = key.key {
+                let c = c.to_lowercase().next().unwrap_or(c);
+                return match self.menus.iter().position(|menu| menu.accelerator() == Some(c)) {
+                    Some(index) => self.open(index),
+                    None => Response::Ignored,
+                };
+            }
+        }
+        let outcome = match self.open {
+            Some((index, ref mut cascade)) => {
+                let items = self.menus[index].items().unwrap_or(&[]);
+                (index, cascade.handle_key(items, key))
+            },
+            None => return Response::Ignored,
+        };
+        self.finish(outcome.0, outcome.1)
+    }
+}
+
+impl Widget for MenuBar {
+    fn render(&mut self, surface: &Surface) {
+        self.area = surface.area();
+        let looks = self.looks(surface);
+        let look = self.look;
+        surface.print(0, 0, look.style, look.fg, look.bg, &" ".repeat(surface.width()));
+        self.titles.clear();
+        let mut x = 0;
+        for (i, menu) in self.menus.iter().enumerate() {
+            let title = menu.label().unwrap_or("");
+            let look = match self.open {
+                Some((open, _)) if open == i => self.selected_look,
+                _ if !menu.is_enabled() => looks.disabled,
+                _ => self.look,
+            };
+            let width = menu.width() + 2;
+            surface.print(x, 0, look.style, look.fg, look.bg, &" ".repeat(width));
+            print_label(surface, x + 1, 0, title, look);
+            self.titles.push(Rect::new(x, 0, width, 1));
+            x += width;
+        }
+
+        if let Some((index, ref mut cascade)) = self.open {
+            let items = self.menus[index].items().unwrap_or(&[]);
+            let below = surface.sub(0, 1, surface.width(), surface.height().saturating_sub(1));
+            cascade.render(&below, items, looks);
+        }
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Response {
+        if let Some(key) = key_of(event) {
+            return self.handle_key(&key);
+        }
+        let (x, y) = match mouse_in(event, self.area) {
+            Some((Mouse::Left, x, y)) => (x, y),
+            Some(_) if self.open.is_some() => return Response::Handled,
+            _ => return Response::Ignored,
+        };
+        if let Some(index) = self.titles.iter().position(|title| title.contains(x, y)) {
+            if self.open_menu() == Some(index) {
+                self.close();
+                return Response::Handled;
+            }
+            return self.open(index);
+        }
+        let outcome = match self.open {
+            Some((index, ref mut cascade)) => {
+                let items = self.menus[index].items().unwrap_or(&[]);
+                // the menus are drawn below the bar
+                let outcome = if y > 0 { cascade.click(items, x, y - 1) } else { Outcome::Closed };
+                (index, outcome)
+            },
+            None => return Response::Ignored,
+        };
+        self.finish(outcome.0, outcome.1)
+    }
+
+    fn desired_size(&self) -> (usize, usize) {
+        (self.menus.iter().map(|menu| menu.width() + 2).sum(), 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_label, ContextMenu, MenuBar, MenuItem};
+    use super::super::{Response, Widget};
+    use backend::HeadlessBackend;
+    use std::default::Default;
+    use {Event, ExtendedKey, Key, Modifiers, Mouse, RustBox};
+
+    fn key(key: Key) -> Event {
+        Event::KeyEvent(ExtendedKey::new(key, Modifiers::new()))
+    }
+
+    fn alt(c: char) -> Event {
+        Event::KeyEvent(ExtendedKey::new(Key::Char(c), Modifiers::new_all(false, false, true)))
+    }
+
+    fn context_menu() -> ContextMenu {
+        let mut menu = ContextMenu::new(vec![
+            MenuItem::action("&Copy"),
+            MenuItem::action("&Paste").disabled(),
+            MenuItem::separator(),
+            MenuItem::submenu("&Sort", vec![
+                MenuItem::action("By &name"),
+                MenuItem::action("By &size").disabled(),
+                MenuItem::action("By &date"),
+            ]),
+        ]);
+        menu.open_at(0, 0);
+        menu
+    }
+
+    fn path(menu: &ContextMenu) -> Vec<usize> {
+        menu.cascade.as_ref().map(|cascade| cascade.path()).unwrap_or_default()
+    }
+
+    #[test]
+    fn submenu_navigation() {
+        let mut menu = context_menu();
+        assert_eq!(path(&menu), vec![0]);
+        menu.handle_event(&key(Key::Down));
+        assert_eq!(path(&menu), vec![3]);
+        menu.handle_event(&key(Key::Right));
+        assert_eq!(path(&menu), vec![3, 0]);
+        menu.handle_event(&key(Key::End));
+        assert_eq!(path(&menu), vec![3, 2]);
+        // Left and Esc close the submenu only
+        menu.handle_event(&key(Key::Left));
+        assert_eq!(path(&menu), vec![3]);
+        menu.handle_event(&key(Key::Enter));
+        assert_eq!(path(&menu), vec![3, 0]);
+        menu.handle_event(&key(Key::Esc));
+        assert_eq!(path(&menu), vec![3]);
+        assert!(menu.is_open());
+
+        menu.handle_event(&key(Key::Right));
+        assert_eq!(menu.handle_event(&key(Key::Enter)), Response::Activated);
+        assert_eq!(menu.chosen(), Some(&[3, 0][..]));
+        assert!(!menu.is_open());
+
+        // the submenu is drawn next to its entry and can be clicked
+        let backend = HeadlessBackend::new(30, 8);
+        let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+        menu.open_at(0, 0);
+        menu.handle_event(&key(Key::Char('s')));
+        menu.render(&rb.surface());
+        let submenu = menu.cascade.as_ref().unwrap().levels[1].area;
+        let click = Event::MouseEvent(Mouse::Left, submenu.x as i16 + 2, submenu.y as i16 + 3);
+        assert_eq!(menu.handle_event(&click), Response::Activated);
+        assert_eq!(menu.chosen(), Some(&[3, 2][..]));
+    }
+
+    #[test]
+    fn accelerators() {
+        assert_eq!(parse_label("&Open"), ("Open".to_string(), Some((0, 'o'))));
+        assert_eq!(parse_label("Save &As"), ("Save As".to_string(), Some((5, 'a'))));
+        assert_eq!(parse_label("R&&D"), ("R&D".to_string(), None));
+        assert_eq!(parse_label("&&&Both"), ("&Both".to_string(), Some((1, 'b'))));
+        assert_eq!(parse_label("Trailing&"), ("Trailing".to_string(), None));
+
+        // accelerators ignore case, open submenus and choose entries
+        let mut menu = context_menu();
+        menu.handle_event(&key(Key::Char('S')));
+        assert_eq!(path(&menu), vec![3, 0]);
+        assert_eq!(menu.handle_event(&key(Key::Char('d'))), Response::Activated);
+        assert_eq!(menu.chosen(), Some(&[3, 2][..]));
+
+        // letters without an entry are swallowed while the menu is open
+        menu.open_at(0, 0);
+        assert_eq!(menu.handle_event(&key(Key::Char('z'))), Response::Handled);
+        assert_eq!(path(&menu), vec![0]);
+
+        let mut bar = MenuBar::new(vec![
+            MenuItem::submenu("&File", vec![MenuItem::action("&Quit")]),
+            MenuItem::action("&Run"),
+        ]);
+        assert_eq!(bar.handle_event(&alt('F')), Response::Handled);
+        assert_eq!(bar.open_menu(), Some(0));
+        // a title without entries is chosen right away
+        assert_eq!(bar.handle_event(&alt('r')), Response::Activated);
+        assert_eq!(bar.chosen(), Some(&[1][..]));
+        assert_eq!(bar.handle_event(&alt('x')), Response::Ignored);
+    }
+
+    #[test]
+    fn disabled_items_are_skipped() {
+        let mut menu = context_menu();
+        menu.handle_event(&key(Key::Down));
+        menu.handle_event(&key(Key::Up));
+        assert_eq!(path(&menu), vec![0]);
+        menu.handle_event(&key(Key::End));
+        assert_eq!(path(&menu), vec![3]);
+        menu.handle_event(&key(Key::Right));
+        menu.handle_event(&key(Key::Down));
+        assert_eq!(path(&menu), vec![3, 2]);
+
+        // nor chosen by accelerator
+        menu.handle_event(&key(Key::Left));
+        assert_eq!(menu.handle_event(&key(Key::Char('p'))), Response::Handled);
+        assert_eq!(menu.chosen(), None);
+
+        // a menu with nothing enabled has no selection
+        let mut menu = ContextMenu::new(vec![MenuItem::action("a").disabled(), MenuItem::separator()]);
+        menu.open_at(0, 0);
+        assert!(path(&menu).is_empty());
+        menu.handle_event(&key(Key::Down));
+        assert_eq!(menu.handle_event(&key(Key::Enter)), Response::Handled);
+        assert!(path(&menu).is_empty());
+
+        // moving along the bar skips disabled menus
+        let mut bar = MenuBar::new(vec![
+            MenuItem::submenu("&A", vec![MenuItem::action("1")]),
+            MenuItem::submenu("&B", vec![MenuItem::action("2")]).disabled(),
+            MenuItem::submenu("&C", vec![MenuItem::action("3")]),
+        ]);
+        assert_eq!(bar.handle_event(&alt('b')), Response::Handled);
+        assert_eq!(bar.open_menu(), None);
+        bar.open(0);
+        bar.handle_event(&key(Key::Right));
+        assert_eq!(bar.open_menu(), Some(2));
+        bar.handle_event(&key(Key::Left));
+        assert_eq!(bar.open_menu(), Some(0));
+    }
+}
@@ -0,0 +1,125 @@
+//! Reusable components that draw themselves into a `Surface` and react to
+//! events.
+//!
+//! Widgets keep their own state between frames. Each frame they are rendered
+//! into a surface, which is also where they expect the mouse events passed to
+//! `handle_event` to land.
+
+use super::{Color, Event, Rect, Style, RB_NORMAL, RB_REVERSE};
+use super::{ExtendedKey, Mouse, Surface};
+
+mod block;
+mod dialog;
+mod input;
+mod label;
+mod list;
+mod menu;
+mod overlay;
+mod progress;
+mod scrollbar;
+mod selection;
+mod table;
+mod tabs;
+mod textarea;
+mod tree;
+
+pub use self::block::Block;
+pub use self::dialog::Dialog;
+pub use self::input::TextInput;
+pub use self::label::Label;
+pub use self::list::List;
+pub use self::menu::{ContextMenu, MenuBar, MenuItem};
+pub use self::overlay::{Layer, Overlays, Placement};
+pub use self::progress::ProgressBar;
+pub use self::scrollbar::Scrollbar;
+pub use self::table::Table;
+pub use self::tabs::Tabs;
+pub use self::textarea::TextArea;
+pub use self::tree::{Loader, Tree, TreeNode};
+
+/// A component drawn into a region of the screen.
+pub trait Widget {
+    /// Draws the widget into `surface`, which is remembered as the widget's
+    /// area for mouse events.
+    fn render(&mut self, surface: &Surface);
+
+    /// Reacts to `event`, usually a key press or a mouse event inside the area
+    /// the widget was last rendered into.
+    fn handle_event(&mut self, _event: &Event) -> Response {
+        Response::Ignored
+    }
+
+    /// The size the widget would like to be rendered at, as (width, height).
+    fn desired_size(&self) -> (usize, usize);
+}
+
+/// What a widget did with an event.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Response {
+    /// The event wasn't meant for the widget and can be passed on.
+    Ignored,
+    /// The event was used and the widget should be drawn again.
+    Handled,
+    /// Like `Handled`, and the widget's action was triggered, e.g. Enter on a
+    /// list item or in a text input.
+    Activated,
+}
+
+impl Response {
+    pub fn is_handled(&self) -> bool {
+        *self != Response::Ignored
+    }
+}
+
+/// The style and colors a part of a widget is drawn with.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CellStyle {
+    pub style: Style,
+    pub fg: Color,
+    pub bg: Color,
+}
+
+impl CellStyle {
+    pub fn new(style: Style, fg: Color, bg: Color) -> CellStyle {
+        CellStyle {
+            style,
+            fg,
+            bg,
+        }
+    }
+
+    /// The same with reverse video toggled, which is how selections are shown
+    /// by default.
+    pub fn reversed(&self) -> CellStyle {
+        CellStyle { style: self.style ^ RB_REVERSE, ..*self }
+    }
+}
+
+impl Default for CellStyle {
+    fn default() -> Self {
+        CellStyle::new(RB_NORMAL, Color::Default, Color::Default)
+    }
+}
+
+/// The key of a key event.
+fn key_of(event: &Event) -> Option<ExtendedKey> {
+    match *event {
+        Event::KeyEvent(key) => Some(key),
+        _ => None,
+    }
+}
+
+/// The button and local position of a mouse event inside `area`.
+fn mouse_in(event: &Event, area: Rect) -> Option<(Mouse, usize, usize)> {
+    match *event {
+        Event::MouseEvent(mouse, x, y) if x >= 0 && y >= 0 => {
+            let (x, y) = (x as usize, y as usize);
+            if area.contains(x, y) {
+                Some((mouse, x - area.x, y - area.y))
+            } else {
+                None
+            }
+        },
+        _ => None,
+    }
+}
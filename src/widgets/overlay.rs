@@ -0,0 +1,405 @@
+use std::any::Any;
+
+use super::super::{Cell, CellBuffer, Color, Event, Key, Mouse, OutputMode, Rect, RustBox, Surface};
+use super::super::{RB_BOLD, RB_DIM, RB_NORMAL};
+
+use super::{mouse_in, Response, Widget};
+
+/// Where a layer goes on the surface the stack is rendered into.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Placement {
+    /// In the middle, at the widget's desired size.
+    Centered,
+    /// With the top left corner at local `x`, `y`, moved up and left where the
+    /// widget wouldn't fit otherwise. Meant for popups opened at the mouse.
+    At(usize, usize),
+    /// Covering a rectangle in local coordinates.
+    Area(Rect),
+}
+
+/// Lets layers hand their widget back as the type it was pushed as.
+trait AnyWidget: Widget {
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<W: Widget + Any> AnyWidget for W {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// A widget on an `Overlays` stack.
+pub struct Layer {
+    widget: Box<dyn AnyWidget>,
+    pub placement: Placement,
+    /// Dims everything below the layer.
+    pub dim: bool,
+    /// Keeps the events the layer ignores from reaching the base frame.
+    pub modal: bool,
+    /// Closes the layer on Esc or a click outside of it.
+    pub dismissable: bool,
+    // Where the layer was last rendered, in absolute coordinates
+    area: Rect,
+}
+
+impl Layer {
+    pub fn new<W: Widget + 'static>(widget: W, placement: Placement) -> Layer {
+        Layer {
+            widget: Box::new(widget),
+            placement,
+            dim: false,
+            modal: false,
+            dismissable: false,
+            area: Rect::default(),
+        }
+    }
+
+    /// A centered layer over the dimmed frame, taking all events.
+    pub fn modal<W: Widget + 'static>(widget: W) -> Layer {
+        Layer { dim: true, modal: true, ..Layer::new(widget, Placement::Centered) }
+    }
+
+    /// A layer at local `x`, `y` that goes away on Esc or a click elsewhere,
+    /// like a context menu.
+    pub fn popup<W: Widget + 'static>(widget: W, x: usize, y: usize) -> Layer {
+        Layer { modal: true, dismissable: true, ..Layer::new(widget, Placement::At(x, y)) }
+    }
+
+    /// The widget, if it is a `W`.
+    pub fn widget<W: Widget + 'static>(&self) -> Option<&W> {
+        self.widget.as_any().downcast_ref()
+    }
+
+    pub fn widget_mut<W: Widget + 'static>(&mut self) -> Option<&mut W> {
+        self.widget.as_any_mut().downcast_mut()
+    }
+
+    /// Where the layer was last rendered, in absolute coordinates.
+    pub fn area(&self) -> Rect {
+        self.area
+    }
+
+    /// The rectangle covered inside a `width` x `height` surface.
+    fn place(&self, width: usize, height: usize) -> Rect {
+        let (w, h) = self.widget.desired_size();
+        let (w, h) = (w.min(width), h.min(height));
+        match self.placement {
+            Placement::Centered => Rect::new((width - w) / 2, (height - h) / 2, w, h),
+            Placement::At(x, y) => Rect::new(x.min(width - w), y.min(height - h), w, h),
+            Placement::Area(rect) => rect,
+        }
+    }
+
+    /// Whether `event` closes a dismissable layer.
+    fn dismisses(&self, event: &Event) -> bool {
+        if !self.dismissable {
+            return false;
+        }
+        match *event {
+            Event::KeyEvent(key) => key.key == Key::Esc,
+            Event::MouseEvent(Mouse::Left, _, _) | Event::MouseEvent(Mouse::Right, _, _) |
+            Event::MouseEvent(Mouse::Middle, _, _) => mouse_in(event, self.area).is_none(),
+            _ => false,
+        }
+    }
+}
+
+/// A stack of popups composited over a base frame.
+///
+/// Draw the base frame as usual, then render the stack on top of it. Layers
+/// are drawn bottom to top, a dimming layer dims everything drawn before it,
+/// and events go to the top layer only. The frame under the layers is kept and
+/// put back by every render, so when a layer is popped the frame comes back
+/// without being redrawn. That also covers whatever was drawn since, so call
+/// `invalidate` after redrawing the whole frame to have the next render keep
+/// the new one. To redraw only part of it call `restore` first, which puts the
+/// kept frame back on screen to draw over.
+///
+/// ```
+/// use rustbox::{Color, Event, ExtendedKey, Key, Modifiers, RustBox, RB_DIM, RB_NORMAL};
+/// use rustbox::backend::HeadlessBackend;
+/// use rustbox::widgets::{Dialog, Layer, Overlays, Response};
+/// use std::default::Default;
+///
+/// let backend = HeadlessBackend::new(14, 5);
+/// let screen = backend.screen();
+/// let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+/// rb.print(0, 2, RB_NORMAL, Color::Default, Color::Default, "the base frame");
+///
+/// let mut overlays = Overlays::new();
+/// overlays.push(Layer::modal(Dialog::message("Hi", "Done")));
+/// overlays.render(&rb.surface());
+/// rb.present();
+/// assert_eq!(screen.lines(), vec!["  ┌ Hi ────┐  ",
+///                                 "  │ Done   │  ",
+///                                 "th│        │me",
+///                                 "  │ [ OK ] │  ",
+///                                 "  └────────┘  "]);
+/// assert!(screen.cell(0, 2).unwrap().style.contains(RB_DIM));
+///
+/// let enter = Event::KeyEvent(ExtendedKey::new(Key::Enter, Modifiers::new()));
+/// assert_eq!(overlays.handle_event(&enter), Response::Activated);
+/// let dialog = overlays.top().and_then(|layer| layer.widget::<Dialog>()).unwrap();
+/// assert_eq!(dialog.choice(), Some(0));
+/// overlays.pop();
+/// overlays.render(&rb.surface());
+/// rb.present();
+/// assert_eq!(screen.line(2).unwrap(), "the base frame");
+///
+/// // redraw part of the frame while a popup is open
+/// overlays.push(Layer::modal(Dialog::message("Hi", "Again")));
+/// overlays.render(&rb.surface());
+/// overlays.restore(&rb.surface());
+/// rb.print(0, 0, RB_NORMAL, Color::Default, Color::Default, "new title");
+/// overlays.render(&rb.surface());
+/// overlays.pop();
+/// overlays.render(&rb.surface());
+/// rb.present();
+/// assert_eq!(screen.lines()[..3], ["new title     ", "              ", "the base frame"]);
+/// ```
+pub struct Overlays {
+    layers: Vec<Layer>,
+    // The frame under the layers as of the last render
+    base: Vec<Cell>,
+    // Set when the screen holds a new frame to take as the base
+    redrawn: bool,
+    area: Rect,
+}
+
+impl Overlays {
+    pub fn new() -> Overlays {
+        Overlays {
+            layers: vec![],
+            base: vec![],
+            redrawn: true,
+            area: Rect::default(),
+        }
+    }
+
+    /// Puts `layer` on top, giving it the focus.
+    pub fn push(&mut self, layer: Layer) {
+        self.layers.push(layer);
+    }
+
+    pub fn pop(&mut self) -> Option<Layer> {
+        self.layers.pop()
+    }
+
+    pub fn clear(&mut self) {
+        self.layers.clear();
+    }
+
+    pub fn top(&self) -> Option<&Layer> {
+        self.layers.last()
+    }
+
+    pub fn top_mut(&mut self) -> Option<&mut Layer> {
+        self.layers.last_mut()
+    }
+
+    /// The layers, bottom first.
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Marks the whole frame under the layers as redrawn, so the next render
+    /// keeps what is on screen then as the new frame.
+    pub fn invalidate(&mut self) {
+        self.redrawn = true;
+    }
+
+    /// Puts the frame under the layers back into `surface`, for redrawing parts
+    /// of it before the next render. Does the same as `invalidate` otherwise.
+    pub fn restore(&mut self, surface: &Surface) {
+        if !self.redrawn && surface.area() == self.area {
+            write_region(&mut surface.rustbox().cell_buffer(), self.area, &self.base);
+        }
+        self.redrawn = true;
+    }
+
+    /// Composites the layers over the frame under them: what is drawn in
+    /// `surface` after `invalidate` or `restore`, the kept frame otherwise.
+    pub fn render(&mut self, surface: &Surface) {
+        let rb = surface.rustbox();
+        let area = surface.area();
+        {
+            let mut buffer = rb.cell_buffer();
+            if self.redrawn || area != self.area {
+                self.base = read_region(&buffer, area);
+            } else {
+                write_region(&mut buffer, area, &self.base);
+            }
+            if self.layers.first().map_or(false, |layer| layer.dim) {
+                dim_region(rb, &mut buffer, area);
+            }
+        }
+        self.area = area;
+        self.redrawn = false;
+
+        if !self.layers.is_empty() {
+            surface.set_cursor(-1, -1);
+        }
+        for (i, layer) in self.layers.iter_mut().enumerate() {
+            if layer.dim && i > 0 {
+                // the layers below have to be drawn before they can be dimmed
+                dim_region(rb, &mut rb.cell_buffer(), area);
+            }
+            let sub = surface.sub_rect(layer.place(surface.width(), surface.height()));
+            layer.area = sub.area();
+            sub.fill(RB_NORMAL, Color::Default, Color::Default, ' ');
+            layer.widget.render(&sub);
+        }
+    }
+
+    /// Passes `event` to the top layer. Events a modal layer ignores are
+    /// reported as handled, except for resizes, so they don't reach the base
+    /// frame; `Ignored` means the caller can handle the event itself.
+    pub fn handle_event(&mut self, event: &Event) -> Response {
+        let top = match self.layers.last_mut() {
+            Some(top) => top,
+            None => return Response::Ignored,
+        };
+        if top.dismisses(event) {
+            self.layers.pop();
+            return Response::Handled;
+        }
+        match top.widget.handle_event(event) {
+            Response::Ignored if top.modal && !is_resize(event) => Response::Handled,
+            response => response,
+        }
+    }
+}
+
+impl Default for Overlays {
+    fn default() -> Self {
+        Overlays::new()
+    }
+}
+
+fn is_resize(event: &Event) -> bool {
+    matches!(*event, Event::ResizeEvent(_, _))
+}
+
+fn read_region(buffer: &CellBuffer, area: Rect) -> Vec<Cell> {
+    let mut cells = Vec::with_capacity(area.width * area.height);
+    for y in area.y..area.bottom() {
+        if let Some(row) = buffer.row(y) {
+            cells.extend_from_slice(&row[area.x.min(row.len())..area.right().min(row.len())]);
+        }
+    }
+    cells
+}
+
+fn write_region(buffer: &mut CellBuffer, area: Rect, cells: &[Cell]) {
+    let positions = (area.y..area.bottom()).flat_map(|y| (area.x..area.right()).map(move |x| (x, y)));
+    for ((x, y), &cell) in positions.zip(cells) {
+        if let Some(target) = buffer.get_mut(x, y) {
+            *target = cell;
+        }
+    }
+}
+
+/// Dims the cells of `area`. Where the terminal can't show dim text they are
+/// drawn in dark gray instead, which is bold black with 16 colors.
+fn dim_region(rb: &RustBox, buffer: &mut CellBuffer, area: Rect) {
+    let dim = rb.supported_styles().contains(RB_DIM);
+    let (gray, bright) = match rb.output_mode() {
+        OutputMode::Normal | OutputMode::NoOutput => (Color::Black, RB_BOLD),
+        _ => (Color::Byte(8), RB_NORMAL),
+    };
+    for y in area.y..area.bottom() {
+        for x in area.x..area.right() {
+            if let Some(cell) = buffer.get_mut(x, y) {
+                if dim {
+                    cell.style = cell.style | RB_DIM;
+                } else {
+                    cell.fg = gray;
+                    cell.style = cell.style | bright;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Layer, Overlays, Placement};
+    use backend::{HeadlessBackend, HeadlessScreen};
+    use std::default::Default;
+    use widgets::Dialog;
+    use {Color, Rect, RustBox, RB_NORMAL};
+
+    fn headless(width: usize, height: usize) -> (RustBox, HeadlessScreen) {
+        let backend = HeadlessBackend::new(width, height);
+        let screen = backend.screen();
+        (RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap(), screen)
+    }
+
+    fn frame(rb: &RustBox, rows: &[&str]) {
+        for (y, row) in rows.iter().enumerate() {
+            rb.print(0, y, RB_NORMAL, Color::Default, Color::Default, row);
+        }
+    }
+
+    #[test]
+    fn partial_redraw_then_pop_leaves_no_ghost() {
+        let (rb, screen) = headless(8, 4);
+        frame(&rb, &["aaaaaaaa", "bbbbbbbb", "cccccccc", "dddddddd"]);
+        let mut overlays = Overlays::new();
+        let popup = Dialog::new(None, "", vec![]);
+        overlays.push(Layer::new(popup, Placement::Area(Rect::new(2, 0, 4, 3))));
+        overlays.render(&rb.surface());
+        rb.present();
+        assert_eq!(screen.line(1).unwrap(), "bb│  │bb");
+
+        // redraw the rows under and next to the popup
+        overlays.restore(&rb.surface());
+        rb.print(0, 1, RB_NORMAL, Color::Default, Color::Default, "BBBB");
+        rb.print(0, 3, RB_NORMAL, Color::Default, Color::Default, "DDDDDDDD");
+        overlays.render(&rb.surface());
+        rb.present();
+        assert_eq!(screen.line(3).unwrap(), "DDDDDDDD");
+
+        overlays.pop();
+        overlays.render(&rb.surface());
+        rb.present();
+        assert_eq!(screen.lines(), vec!["aaaaaaaa", "BBBBbbbb", "cccccccc", "DDDDDDDD"]);
+    }
+
+    #[test]
+    fn full_redraw_is_kept_after_invalidate() {
+        let (rb, screen) = headless(6, 3);
+        frame(&rb, &["old   ", "old   ", "old   "]);
+        let mut overlays = Overlays::new();
+        overlays.push(Layer::new(Dialog::new(None, "", vec![]), Placement::Area(Rect::new(0, 1, 6, 1))));
+        overlays.render(&rb.surface());
+
+        rb.clear();
+        frame(&rb, &["new", "new", "new"]);
+        overlays.invalidate();
+        overlays.render(&rb.surface());
+        overlays.pop();
+        overlays.render(&rb.surface());
+        rb.present();
+        assert_eq!(screen.lines(), vec!["new   ", "new   ", "new   "]);
+
+        // without `invalidate` the kept frame wins over what was drawn
+        frame(&rb, &["xxx"]);
+        overlays.render(&rb.surface());
+        rb.present();
+        assert_eq!(screen.line(0).unwrap(), "new   ");
+    }
+}
@@ -0,0 +1,78 @@
+use super::super::{Span, StyledText, Surface};
+use unicode;
+
+use super::{CellStyle, Widget};
+
+/// A bar filling up from left to right, with a label in the middle.
+#[derive(Clone, Debug)]
+pub struct ProgressBar {
+    ratio: f64,
+    /// Shown instead of the percentage when set.
+    pub label: Option<String>,
+    pub look: CellStyle,
+    pub filled_look: CellStyle,
+}
+
+impl ProgressBar {
+    pub fn new() -> ProgressBar {
+        let look = CellStyle::default();
+        ProgressBar {
+            ratio: 0.0,
+            label: None,
+            look,
+            filled_look: look.reversed(),
+        }
+    }
+
+    pub fn ratio(&self) -> f64 {
+        self.ratio
+    }
+
+    /// Sets how far along the bar is, from 0 to 1.
+    pub fn set_ratio(&mut self, ratio: f64) {
+        self.ratio = if ratio.is_nan() { 0.0 } else { ratio.clamp(0.0, 1.0) };
+    }
+
+    fn label(&self) -> String {
+        match self.label {
+            Some(ref label) => label.clone(),
+            None => format!("{}%", (self.ratio * 100.0).round()),
+        }
+    }
+}
+
+impl Default for ProgressBar {
+    fn default() -> Self {
+        ProgressBar::new()
+    }
+}
+
+impl Widget for ProgressBar {
+    fn render(&mut self, surface: &Surface) {
+        let width = surface.width();
+        let filled = (self.ratio * width as f64).round() as usize;
+        let label = unicode::truncate(&self.label(), width, None).into_owned();
+        let start = (width - unicode::text_width(&label)) / 2;
+        let label_row = surface.height().saturating_sub(1) / 2;
+
+        for y in 0..surface.height() {
+            let mut text = StyledText::new();
+            let mut graphemes = if y == label_row { Some(unicode::graphemes(&label)) } else { None };
+            let mut column = 0;
+            while column < width {
+                let (grapheme, w) = match graphemes {
+                    Some(ref mut graphemes) if column >= start => graphemes.next().unwrap_or((" ", 1)),
+                    _ => (" ", 1),
+                };
+                let look = if column < filled { self.filled_look } else { self.look };
+                text.push(Span::styled(grapheme, look.style, look.fg, look.bg));
+                column += w;
+            }
+            surface.print_spans(0, y, &text);
+        }
+    }
+
+    fn desired_size(&self) -> (usize, usize) {
+        (unicode::text_width(&self.label()) + 2, 1)
+    }
+}
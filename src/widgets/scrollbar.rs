@@ -0,0 +1,110 @@
+use super::super::{Event, Mouse, Rect, Surface};
+use layout::Direction;
+
+use super::{mouse_in, CellStyle, Response, Widget};
+
+/// Shows which part of some longer content is in view.
+#[derive(Clone, Debug)]
+pub struct Scrollbar {
+    pub direction: Direction,
+    /// Length of the whole content, e.g. the number of lines of a list.
+    pub content_length: usize,
+    /// How much of the content is in view at a time.
+    pub viewport_length: usize,
+    /// Where the view starts in the content, from 0 up to `content_length - viewport_length`.
+    pub position: usize,
+    pub look: CellStyle,
+    pub thumb_look: CellStyle,
+    area: Rect,
+}
+
+impl Scrollbar {
+    pub fn new(direction: Direction) -> Scrollbar {
+        let look = CellStyle::default();
+        Scrollbar {
+            direction,
+            content_length: 0,
+            viewport_length: 0,
+            position: 0,
+            look,
+            thumb_look: look,
+            area: Rect::default(),
+        }
+    }
+
+    fn max_position(&self) -> usize {
+        self.content_length.saturating_sub(self.viewport_length)
+    }
+
+    /// Start and length of the thumb on a track `track` cells long.
+    fn thumb(&self, track: usize) -> (usize, usize) {
+        if self.content_length <= self.viewport_length || track == 0 {
+            return (0, track);
+        }
+        let length = (track * self.viewport_length / self.content_length).max(1);
+        let start = (track - length) * self.position.min(self.max_position()) / self.max_position();
+        (start, length)
+    }
+
+    fn scroll_by(&mut self, delta: isize) {
+        self.position = if delta < 0 {
+            self.position.saturating_sub(-delta as usize)
+        } else {
+            (self.position + delta as usize).min(self.max_position())
+        };
+    }
+}
+
+impl Widget for Scrollbar {
+    fn render(&mut self, surface: &Surface) {
+        self.area = surface.area();
+        let (track, ch) = match self.direction {
+            Direction::Vertical => (surface.height(), '│'),
+            Direction::Horizontal => (surface.width(), '─'),
+        };
+        let (start, length) = self.thumb(track);
+        for i in 0..track {
+            let (look, ch) = if i >= start && i < start + length {
+                (self.thumb_look, '█')
+            } else {
+                (self.look, ch)
+            };
+            let (x, y) = match self.direction {
+                Direction::Vertical => (0, i),
+                Direction::Horizontal => (i, 0),
+            };
+            surface.print_char(x, y, look.style, look.fg, look.bg, ch);
+        }
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Response {
+        let (mouse, x, y) = match mouse_in(event, self.area) {
+            Some(mouse) => mouse,
+            None => return Response::Ignored,
+        };
+        let (at, track) = match self.direction {
+            Direction::Vertical => (y, self.area.height),
+            Direction::Horizontal => (x, self.area.width),
+        };
+        match mouse {
+            Mouse::WheelUp => self.scroll_by(-1),
+            Mouse::WheelDown => self.scroll_by(1),
+            // jump so that the thumb is centered on the click, and follow it while dragging
+            Mouse::Left | Mouse::Drag => {
+                let (_, length) = self.thumb(track);
+                let free = track.saturating_sub(length);
+                let start = at.saturating_sub(length / 2).min(free);
+                self.position = (start * self.max_position()).checked_div(free).unwrap_or(0);
+            },
+            _ => return Response::Ignored,
+        }
+        Response::Handled
+    }
+
+    fn desired_size(&self) -> (usize, usize) {
+        match self.direction {
+            Direction::Vertical => (1, self.viewport_length),
+            Direction::Horizontal => (self.viewport_length, 1),
+        }
+    }
+}
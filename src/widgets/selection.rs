@@ -0,0 +1,84 @@
+//! Selection and scrolling shared by the widgets showing rows of items.
+
+use keyboard::{ExtendedKey, Key};
+
+use super::Response;
+
+/// The selected row and the first visible one.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct Selection {
+    pub selected: Option<usize>,
+    pub offset: usize,
+    // Set when the selection moved, so the next render scrolls it into view
+    reveal: bool,
+    // Rows visible at the last render
+    height: usize,
+}
+
+impl Selection {
+    /// Selects `index`, cut down to the last of `len` rows.
+    pub fn select(&mut self, index: Option<usize>, len: usize) {
+        self.selected = match index {
+            Some(_) if len == 0 => None,
+            Some(i) => Some(i.min(len - 1)),
+            None => None,
+        };
+        self.reveal = true;
+    }
+
+    /// Moves the selection by `delta` rows, starting at the first row when
+    /// nothing is selected.
+    pub fn move_by(&mut self, delta: isize, len: usize) {
+        let index = match self.selected {
+            Some(i) if delta < 0 => i.saturating_sub(-delta as usize),
+            Some(i) => i.saturating_add(delta as usize),
+            None => 0,
+        };
+        self.select(Some(index), len);
+    }
+
+    /// Scrolls by `delta` rows without moving the selection.
+    pub fn scroll_by(&mut self, delta: isize, len: usize, height: usize) {
+        let offset = if delta < 0 {
+            self.offset.saturating_sub(-delta as usize)
+        } else {
+            self.offset.saturating_add(delta as usize)
+        };
+        self.offset = offset.min(len.saturating_sub(height));
+    }
+
+    /// Scrolls just enough to show the selected row in a view `height` rows
+    /// high, if the selection moved or the view was resized since last time.
+    pub fn keep_visible(&mut self, len: usize, height: usize) {
+        if !self.reveal && height == self.height {
+            self.offset = self.offset.min(len.saturating_sub(height.max(1)));
+            return;
+        }
+        self.reveal = false;
+        self.height = height;
+        if let Some(i) = self.selected {
+            if i < self.offset {
+                self.offset = i;
+            } else if height > 0 && i >= self.offset + height {
+                self.offset = i + 1 - height;
+            }
+        }
+        self.offset = self.offset.min(len.saturating_sub(height.max(1)));
+    }
+
+    /// The usual navigation keys, `page` rows at a time for PageUp and PageDown.
+    pub fn handle_key(&mut self, key: &ExtendedKey, len: usize, page: usize) -> Response {
+        let page = page.max(1) as isize;
+        match key.key {
+            Key::Up => self.move_by(-1, len),
+            Key::Down => self.move_by(1, len),
+            Key::PageUp => self.move_by(-page, len),
+            Key::PageDown => self.move_by(page, len),
+            Key::Home => self.select(Some(0), len),
+            Key::End => self.select(Some(len.saturating_sub(1)), len),
+            Key::Enter if self.selected.is_some() => return Response::Activated,
+            _ => return Response::Ignored,
+        }
+        Response::Handled
+    }
+}
@@ -0,0 +1,378 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use super::super::{Alignment, Event, Key, Mouse, Rect, Surface, RB_BOLD};
+use layout::{Constraint, Layout};
+use unicode;
+
+use super::selection::Selection;
+use super::{key_of, mouse_in, CellStyle, Response, Widget};
+
+/// Rows of cells under a header, with columns sized by layout constraints.
+///
+/// The header stays in place while the rows scroll. With `sortable` set, a
+/// click on a column header or `s` sorts the rows, by the first column for
+/// `s`. Once sorted, Left and Right move the sort to another column and `r`
+/// reverses the order; the header of that column shows which way it is
+/// sorted. Numbers are compared by value, other text ignoring
+/// case.
+///
+/// ```
+/// use rustbox::{Event, ExtendedKey, Key, Modifiers};
+/// use rustbox::layout::Constraint;
+/// use rustbox::widgets::{Table, Widget};
+///
+/// let mut table = Table::new(vec!["Process".into(), "CPU".into()],
+///                            vec![Constraint::Fill(1), Constraint::Fixed(5)]);
+/// table.set_rows(vec![vec!["init".into(), "0.1".into()], vec!["cargo".into(), "97".into()]]);
+/// table.sortable = true;
+/// table.handle_event(&Event::KeyEvent(ExtendedKey::new(Key::Char('s'), Modifiers::new())));
+/// assert_eq!(table.sort(), Some((0, true)));
+/// assert_eq!(table.rows()[0][0], "cargo");
+/// table.handle_event(&Event::KeyEvent(ExtendedKey::new(Key::Right, Modifiers::new())));
+/// assert_eq!(table.sort(), Some((1, true)));
+/// assert_eq!(table.rows()[0][0], "init");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Table {
+    pub header: Vec<String>,
+    rows: Vec<Vec<String>>,
+    /// One constraint for the width of each column.
+    pub widths: Vec<Constraint>,
+    /// How the cells of each column are aligned, to the left if missing.
+    pub alignments: Vec<Alignment>,
+    pub look: CellStyle,
+    pub header_look: CellStyle,
+    pub selected_look: CellStyle,
+    /// Every other row is drawn with this, if set.
+    pub striped_look: Option<CellStyle>,
+    /// Lets keys and header clicks sort the rows.
+    pub sortable: bool,
+    row_looks: HashMap<usize, CellStyle>,
+    // Column and whether ascending
+    sort: Option<(usize, bool)>,
+    selection: Selection,
+    // Columns of the last render, in local coordinates
+    columns: Vec<Rect>,
+    area: Rect,
+}
+
+impl Table {
+    pub fn new(header: Vec<String>, widths: Vec<Constraint>) -> Table {
+        let look = CellStyle::default();
+        Table {
+            header,
+            rows: vec![],
+            widths,
+            alignments: vec![],
+            look,
+            header_look: CellStyle { style: look.style | RB_BOLD, ..look },
+            selected_look: look.reversed(),
+            striped_look: None,
+            sortable: false,
+            row_looks: HashMap::new(),
+            sort: None,
+            selection: Selection::default(),
+            columns: vec![],
+            area: Rect::default(),
+        }
+    }
+
+    pub fn rows(&self) -> &[Vec<String>] {
+        &self.rows
+    }
+
+    /// Replaces the rows, sorting them like the old ones and keeping the
+    /// selection where it still fits. Row highlights are dropped.
+    pub fn set_rows(&mut self, rows: Vec<Vec<String>>) {
+        self.rows = rows;
+        self.row_looks.clear();
+        if let Some((column, ascending)) = self.sort {
+            self.sort_by(column, ascending);
+        }
+        let selected = self.selection.selected;
+        self.selection.select(selected, self.rows.len());
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.selection.selected
+    }
+
+    pub fn select(&mut self, index: Option<usize>) {
+        self.selection.select(index, self.rows.len());
+    }
+
+    /// Draws row `index` with `look` instead of the usual one, or the usual one
+    /// again for `None`. Highlights move with their rows when sorting.
+    pub fn highlight(&mut self, index: usize, look: Option<CellStyle>) {
+        match look {
+            Some(look) => self.row_looks.insert(index, look),
+            None => self.row_looks.remove(&index),
+        };
+    }
+
+    /// The column the rows are sorted by and whether in ascending order.
+    pub fn sort(&self) -> Option<(usize, bool)> {
+        self.sort
+    }
+
+    /// Sorts the rows by `column`, keeping the selected row selected.
+    pub fn sort_by(&mut self, column: usize, ascending: bool) {
+        let mut order: Vec<usize> = (0..self.rows.len()).collect();
+        {
+            let rows = &self.rows;
+            let empty = String::new();
+            order.sort_by(|&a, &b| {
+                let a = rows[a].get(column).unwrap_or(&empty);
+                let b = rows[b].get(column).unwrap_or(&empty);
+                let ordering = compare_cells(a, b);
+                if ascending { ordering } else { ordering.reverse() }
+            });
+        }
+
+        let mut rows: Vec<Option<Vec<String>>> = self.rows.drain(..).map(Some).collect();
+        self.rows = order.iter().map(|&i| rows[i].take().unwrap_or_default()).collect();
+        let row_looks = self.row_looks.clone();
+        self.row_looks = order.iter().enumerate()
+            .filter_map(|(new, old)| row_looks.get(old).map(|&look| (new, look)))
+            .collect();
+        let selected = self.selection.selected.and_then(|old| order.iter().position(|&i| i == old));
+        self.selection.select(selected, self.rows.len());
+        self.sort = Some((column, ascending));
+    }
+
+    /// Rows below the header.
+    fn body_height(&self) -> usize {
+        self.area.height.saturating_sub(1)
+    }
+
+    fn row_look(&self, index: usize) -> CellStyle {
+        if Some(index) == self.selection.selected {
+            return self.selected_look;
+        }
+        if let Some(&look) = self.row_looks.get(&index) {
+            return look;
+        }
+        match self.striped_look {
+            Some(look) if index % 2 == 1 => look,
+            _ => self.look,
+        }
+    }
+
+    fn header_cell(&self, column: usize) -> String {
+        let title = self.header.get(column).cloned().unwrap_or_default();
+        match self.sort {
+            Some((sorted, true)) if sorted == column => format!("{} ▲", title),
+            Some((sorted, false)) if sorted == column => format!("{} ▼", title),
+            _ => title,
+        }
+    }
+
+    /// Prints a row of cells, leaving a column of space between them.
+    fn render_row(&self, surface: &Surface, y: usize, cells: &[String], look: CellStyle) {
+        let line = " ".repeat(surface.width());
+        surface.print(0, y, look.style, look.fg, look.bg, &line);
+        for (i, (column, cell)) in self.columns.iter().zip(cells).enumerate() {
+            let spacing = if i + 1 < self.columns.len() { 1 } else { 0 };
+            let width = column.width.saturating_sub(spacing);
+            let text = unicode::truncate(cell, width, Some("…"));
+            let slack = width - unicode::text_width(&text);
+            let x = match self.alignments.get(i) {
+                Some(&Alignment::Right) => column.x + slack,
+                Some(&Alignment::Center) => column.x + slack / 2,
+                _ => column.x,
+            };
+            surface.print(x, y, look.style, look.fg, look.bg, &text);
+        }
+    }
+
+    fn sort_column(&mut self, column: usize, toggle: bool) {
+        let ascending = match self.sort {
+            Some((sorted, ascending)) if sorted == column && toggle => !ascending,
+            Some((sorted, ascending)) if sorted == column => ascending,
+            _ => true,
+        };
+        self.sort_by(column, ascending);
+    }
+
+    fn handle_sort_key(&mut self, key: Key) -> bool {
+        let columns = self.widths.len().max(self.header.len());
+        if !self.sortable || columns == 0 {
+            return false;
+        }
+        match (key, self.sort) {
+            (Key::Left, Some((column, _))) => self.sort_column(column.saturating_sub(1), false),
+            (Key::Right, Some((column, _))) => self.sort_column((column + 1).min(columns - 1), false),
+            // Left and Right only move an existing sort, `s` starts one
+            (Key::Char('s'), None) => self.sort_column(0, false),
+            (Key::Char('r'), Some((column, _))) => self.sort_column(column, true),
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Orders numbers by value and text ignoring case.
+fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    }
+}
+
+impl Widget for Table {
+    fn render(&mut self, surface: &Surface) {
+        self.area = surface.area();
+        self.columns = Layout::horizontal(self.widths.clone())
+            .split(Rect::new(0, 0, surface.width(), 1));
+
+        let header: Vec<String> = (0..self.columns.len()).map(|i| self.header_cell(i)).collect();
+        self.render_row(surface, 0, &header, self.header_look);
+        let height = self.body_height();
+        self.selection.keep_visible(self.rows.len(), height);
+        let visible = self.rows.iter().enumerate().skip(self.selection.offset).take(height);
+        for (y, (i, row)) in visible.enumerate() {
+            self.render_row(surface, y + 1, row, self.row_look(i));
+        }
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Response {
+        let len = self.rows.len();
+        if let Some(key) = key_of(event) {
+            if key.modifiers.is_empty() && self.handle_sort_key(key.key) {
+                return Response::Handled;
+            }
+            let page = self.body_height();
+            return self.selection.handle_key(&key, len, page);
+        }
+        match mouse_in(event, self.area) {
+            Some((Mouse::Left, x, 0)) if self.sortable => {
+                match self.columns.iter().position(|column| column.contains(x, 0)) {
+                    Some(column) => {
+                        self.sort_column(column, true);
+                        Response::Handled
+                    },
+                    None => Response::Ignored,
+                }
+            },
+            Some((Mouse::Left, _, y)) if y > 0 && self.selection.offset + y - 1 < len => {
+                self.selection.select(Some(self.selection.offset + y - 1), len);
+                Response::Handled
+            },
+            Some((Mouse::WheelUp, _, _)) => {
+                self.selection.scroll_by(-1, len, self.body_height());
+                Response::Handled
+            },
+            Some((Mouse::WheelDown, _, _)) => {
+                self.selection.scroll_by(1, len, self.body_height());
+                Response::Handled
+            },
+            _ => Response::Ignored,
+        }
+    }
+
+    fn desired_size(&self) -> (usize, usize) {
+        let mut widths: Vec<usize> = (0..self.header.len())
+            .map(|i| unicode::text_width(&self.header_cell(i)))
+            .collect();
+        for row in self.rows.iter() {
+            for (i, cell) in row.iter().enumerate() {
+                let width = unicode::text_width(cell);
+                if i < widths.len() {
+                    widths[i] = widths[i].max(width);
+                } else {
+                    widths.push(width);
+                }
+            }
+        }
+        let spacing = widths.len().saturating_sub(1);
+        (widths.iter().sum::<usize>() + spacing, self.rows.len() + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Table;
+    use super::super::Widget;
+    use backend::HeadlessBackend;
+    use layout::Constraint;
+    use std::default::Default;
+    use {Event, ExtendedKey, Key, Modifiers, Mouse, RustBox};
+
+    fn table(rows: &[(&str, &str)]) -> Table {
+        let mut table = Table::new(vec!["Name".into(), "N".into()],
+                                   vec![Constraint::Fixed(6), Constraint::Fixed(4)]);
+        table.set_rows(rows.iter().map(|&(a, b)| vec![a.to_string(), b.to_string()]).collect());
+        table.sortable = true;
+        table
+    }
+
+    fn names(table: &Table) -> Vec<&str> {
+        table.rows().iter().map(|row| row[0].as_str()).collect()
+    }
+
+    fn press(table: &mut Table, key: Key) {
+        table.handle_event(&Event::KeyEvent(ExtendedKey::new(key, Modifiers::new())));
+    }
+
+    #[test]
+    fn sorting_is_stable() {
+        let mut table = table(&[("b", "1"), ("a", "1"), ("c", "10"), ("d", "1")]);
+        table.sort_by(1, true);
+        assert_eq!(names(&table), vec!["b", "a", "d", "c"]);
+        // equal cells keep their order either way
+        table.sort_by(1, false);
+        assert_eq!(names(&table), vec!["c", "b", "a", "d"]);
+        table.sort_by(0, true);
+        table.sort_by(1, true);
+        assert_eq!(names(&table), vec!["a", "b", "d", "c"]);
+    }
+
+    #[test]
+    fn direction_toggles() {
+        let mut table = table(&[("b", "2"), ("a", "10"), ("C", "1")]);
+        press(&mut table, Key::Char('s'));
+        assert_eq!(table.sort(), Some((0, true)));
+        assert_eq!(names(&table), vec!["a", "b", "C"]);
+        press(&mut table, Key::Char('r'));
+        assert_eq!(table.sort(), Some((0, false)));
+        assert_eq!(names(&table), vec!["C", "b", "a"]);
+        // moving to another column starts out ascending
+        press(&mut table, Key::Right);
+        assert_eq!(table.sort(), Some((1, true)));
+        assert_eq!(names(&table), vec!["C", "b", "a"]);
+
+        let backend = HeadlessBackend::new(10, 4);
+        let screen = backend.screen();
+        let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+        table.render(&rb.surface());
+        rb.present();
+        assert_eq!(screen.line(0).unwrap(), "Name  N ▲ ");
+
+        // clicking the sorted column reverses it, another column starts ascending
+        table.handle_event(&Event::MouseEvent(Mouse::Left, 7, 0));
+        assert_eq!(table.sort(), Some((1, false)));
+        assert_eq!(names(&table), vec!["a", "b", "C"]);
+        table.handle_event(&Event::MouseEvent(Mouse::Left, 1, 0));
+        assert_eq!(table.sort(), Some((0, true)));
+        table.handle_event(&Event::MouseEvent(Mouse::Left, 1, 0));
+        assert_eq!(table.sort(), Some((0, false)));
+    }
+
+    #[test]
+    fn selection_follows_the_sorted_row() {
+        let mut table = table(&[("init", "0.1"), ("cargo", "97"), ("sh", "2")]);
+        table.select(Some(1));
+        table.sort_by(1, false);
+        assert_eq!(names(&table), vec!["cargo", "sh", "init"]);
+        assert_eq!(table.selected(), Some(0));
+        press(&mut table, Key::Down);
+        assert_eq!(table.selected(), Some(1));
+        press(&mut table, Key::Char('r'));
+        assert_eq!(table.rows()[table.selected().unwrap()][0], "sh");
+
+        // new rows are sorted the same way
+        table.set_rows(vec![vec!["b".into(), "5".into()], vec!["a".into(), "7".into()]]);
+        assert_eq!(names(&table), vec!["b", "a"]);
+    }
+}
@@ -0,0 +1,312 @@
+use super::super::{Event, Key, Mouse, Rect, Surface, RB_BOLD};
+use unicode;
+
+use super::{key_of, mouse_in, CellStyle, Response, Widget};
+
+/// Shown after the title of closable tabs.
+const CLOSE: &str = "×";
+
+/// Fewest columns a tab cut off at the end of the strip is shown with.
+const MIN_PARTIAL_WIDTH: usize = 4;
+
+/// A strip of tab titles, one of which is active.
+///
+/// Ctrl+PageUp and Ctrl+PageDown or a click on a title switch tabs. With
+/// `closable` every title gets a close button; clicking it removes the tab and
+/// reports `Response::Activated`, with the tab's index in `closed`. When the
+/// titles don't fit, the strip scrolls to keep the active tab in view and
+/// arrows at its ends show there are more, which the mouse wheel or a click on
+/// an arrow scrolls to. The strip takes the first row of the surface it is
+/// rendered into, `page` gives the rest for the contents of the active tab.
+///
+/// ```
+/// use rustbox::{Event, ExtendedKey, Key, Modifiers, RustBox};
+/// use rustbox::backend::HeadlessBackend;
+/// use rustbox::widgets::{Tabs, Widget};
+/// use std::default::Default;
+///
+/// let backend = HeadlessBackend::new(16, 1);
+/// let screen = backend.screen();
+/// let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+///
+/// let mut tabs = Tabs::new(vec!["main.rs".into(), "lib.rs".into(), "Cargo.toml".into()]);
+/// let next = Event::KeyEvent(ExtendedKey::new(Key::PageDown, Modifiers::new_all(true, false, false)));
+/// tabs.handle_event(&next);
+/// tabs.render(&rb.surface());
+/// rb.present();
+///
+/// assert_eq!(tabs.selected(), 1);
+/// assert_eq!(screen.line(0).unwrap(), "◂ lib.rs │ Ca… ▸");
+/// ```
+#[derive(Clone, Debug)]
+pub struct Tabs {
+    titles: Vec<String>,
+    selected: usize,
+    /// Gives every tab a close button.
+    pub closable: bool,
+    pub look: CellStyle,
+    pub active_look: CellStyle,
+    // First tab shown, and whether to scroll the active tab into view on the next render
+    offset: usize,
+    reveal: bool,
+    closed: Option<usize>,
+    // Titles and close buttons of the last render, in local coordinates
+    tabs: Vec<(usize, Rect, Option<Rect>)>,
+    // Whether arrows were shown at the left and right end
+    arrows: (bool, bool),
+    area: Rect,
+}
+
+impl Tabs {
+    pub fn new(titles: Vec<String>) -> Tabs {
+        let look = CellStyle::default();
+        Tabs {
+            titles,
+            selected: 0,
+            closable: false,
+            look,
+            active_look: CellStyle { style: look.style | RB_BOLD, ..look.reversed() },
+            offset: 0,
+            reveal: true,
+            closed: None,
+            tabs: vec![],
+            arrows: (false, false),
+            area: Rect::default(),
+        }
+    }
+
+    pub fn titles(&self) -> &[String] {
+        &self.titles
+    }
+
+    pub fn set_title<S: Into<String>>(&mut self, index: usize, title: S) {
+        if let Some(old) = self.titles.get_mut(index) {
+            *old = title.into();
+        }
+    }
+
+    /// Adds a tab at the end.
+    pub fn push<S: Into<String>>(&mut self, title: S) {
+        self.titles.push(title.into());
+    }
+
+    /// Removes tab `index`, keeping the active tab active where it is left.
+    pub fn remove(&mut self, index: usize) -> Option<String> {
+        if index >= self.titles.len() {
+            return None;
+        }
+        let title = self.titles.remove(index);
+        if index < self.selected || self.selected >= self.titles.len() {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.offset = self.offset.min(self.titles.len().saturating_sub(1));
+        self.reveal = true;
+        Some(title)
+    }
+
+    /// Index of the active tab.
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.titles.len().saturating_sub(1));
+        self.reveal = true;
+    }
+
+    /// Index the tab closed last had.
+    pub fn closed(&self) -> Option<usize> {
+        self.closed
+    }
+
+    /// The part of `surface` below the strip.
+    pub fn page<'a>(&self, surface: &Surface<'a>) -> Surface<'a> {
+        surface.sub(0, 1, surface.width(), surface.height().saturating_sub(1))
+    }
+
+    /// Columns taken by the tab, without the separator.
+    fn tab_width(&self, index: usize) -> usize {
+        let close = if self.closable { unicode::text_width(CLOSE) + 1 } else { 0 };
+        unicode::text_width(&self.titles[index]) + 2 + close
+    }
+
+    /// Moves the offset so the active tab fits into `width` columns.
+    fn keep_visible(&mut self, width: usize) {
+        if self.selected < self.offset {
+            self.offset = self.selected;
+        }
+        while self.offset < self.selected {
+            let shown: usize = (self.offset..self.selected + 1).map(|i| self.tab_width(i) + 1).sum();
+            if shown - 1 <= width {
+                break;
+            }
+            self.offset += 1;
+        }
+    }
+
+    fn scroll_by(&mut self, delta: isize) {
+        let last = self.titles.len().saturating_sub(1) as isize;
+        self.offset = (self.offset as isize + delta).max(0).min(last) as usize;
+    }
+
+    fn cycle(&mut self, step: isize) {
+        let len = self.titles.len() as isize;
+        if len > 0 {
+            self.selected = ((self.selected as isize + step + len) % len) as usize;
+            self.reveal = true;
+        }
+    }
+}
+
+impl Widget for Tabs {
+    fn render(&mut self, surface: &Surface) {
+        self.area = surface.area();
+        let width = surface.width();
+        let look = self.look;
+        surface.print(0, 0, look.style, look.fg, look.bg, &" ".repeat(width));
+        self.tabs.clear();
+        if self.titles.is_empty() || width == 0 {
+            self.arrows = (false, false);
+            return;
+        }
+
+        let total: usize = (0..self.titles.len()).map(|i| self.tab_width(i) + 1).sum::<usize>() - 1;
+        let (start, end) = if total <= width {
+            self.offset = 0;
+            (0, width)
+        } else if width > 2 {
+            // leave a column at each end for the arrows
+            (1, width - 1)
+        } else {
+            // no room for a tab besides the arrows
+            self.arrows = (false, false);
+            return;
+        };
+        if self.reveal {
+            self.keep_visible(end - start);
+            self.reveal = false;
+        }
+
+        let separator = '│';
+        let mut x = start;
+        let mut next = self.offset;
+        while next < self.titles.len() && x < end {
+            if next > self.offset {
+                // stop rather than show a sliver of the next tab
+                let room = end.saturating_sub(x + 1);
+                if room < self.tab_width(next).min(MIN_PARTIAL_WIDTH) {
+                    break;
+                }
+                surface.print_char(x, 0, look.style, look.fg, look.bg, separator);
+                x += 1;
+            }
+            let look = if next == self.selected { self.active_look } else { self.look };
+            let tab_width = self.tab_width(next).min(end - x);
+            surface.print(x, 0, look.style, look.fg, look.bg, &" ".repeat(tab_width));
+            let close = if self.closable { unicode::text_width(CLOSE) + 1 } else { 0 };
+            let title_width = tab_width.saturating_sub(2 + close);
+            surface.print_truncated(x + 1, 0, look.style, look.fg, look.bg, &self.titles[next], title_width,
+                                    Some("…"));
+            let close = if self.closable && tab_width == self.tab_width(next) {
+                let close_x = x + tab_width - close;
+                surface.print(close_x, 0, look.style, look.fg, look.bg, CLOSE);
+                Some(Rect::new(close_x, 0, unicode::text_width(CLOSE), 1))
+            } else {
+                None
+            };
+            self.tabs.push((next, Rect::new(x, 0, tab_width, 1), close));
+            x += tab_width;
+            next += 1;
+        }
+
+        self.arrows = (self.offset > 0, next < self.titles.len()
+                       || self.tabs.last().map_or(false, |&(i, rect, _)| rect.width < self.tab_width(i)));
+        if self.arrows.0 {
+            surface.print(0, 0, look.style, look.fg, look.bg, "◂");
+        }
+        if self.arrows.1 {
+            surface.print(width - 1, 0, look.style, look.fg, look.bg, "▸");
+        }
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Response {
+        if let Some(key) = key_of(event) {
+            return match key.key {
+                Key::PageUp if key.modifiers.ctrl => {
+                    self.cycle(-1);
+                    Response::Handled
+                },
+                Key::PageDown if key.modifiers.ctrl => {
+                    self.cycle(1);
+                    Response::Handled
+                },
+                _ => Response::Ignored,
+            };
+        }
+        let (mouse, x) = match mouse_in(event, self.area) {
+            Some((mouse, x, 0)) => (mouse, x),
+            _ => return Response::Ignored,
+        };
+        match mouse {
+            Mouse::Left if x == 0 && self.arrows.0 => self.scroll_by(-1),
+            Mouse::Left if x + 1 == self.area.width && self.arrows.1 => self.scroll_by(1),
+            Mouse::Left => {
+                let clicked = self.tabs.iter().find(|&&(_, rect, _)| rect.contains(x, 0)).cloned();
+                match clicked {
+                    Some((index, _, Some(close))) if close.contains(x, 0) => {
+                        self.remove(index);
+                        self.closed = Some(index);
+                        return Response::Activated;
+                    },
+                    Some((index, _, _)) => self.select(index),
+                    None => return Response::Ignored,
+                }
+            },
+            Mouse::WheelUp => self.scroll_by(-1),
+            Mouse::WheelDown => self.scroll_by(1),
+            _ => return Response::Ignored,
+        }
+        Response::Handled
+    }
+
+    fn desired_size(&self) -> (usize, usize) {
+        let width: usize = (0..self.titles.len()).map(|i| self.tab_width(i) + 1).sum();
+        (width.saturating_sub(1), 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Tabs;
+    use super::super::Widget;
+    use backend::HeadlessBackend;
+    use std::default::Default;
+    use RustBox;
+
+    fn render(tabs: &mut Tabs, width: usize) -> String {
+        let backend = HeadlessBackend::new(width, 1);
+        let screen = backend.screen();
+        let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+        tabs.render(&rb.surface());
+        rb.present();
+        screen.line(0).unwrap()
+    }
+
+    #[test]
+    fn narrow_widths() {
+        let mut tabs = Tabs::new(vec!["one".into(), "two".into()]);
+        tabs.select(1);
+        assert_eq!(render(&mut tabs, 1), " ");
+        assert_eq!(render(&mut tabs, 2), "  ");
+        assert_eq!(render(&mut tabs, 3), "◂ ▸");
+        assert_eq!(render(&mut tabs, 6), "◂ t… ▸");
+        assert_eq!(render(&mut tabs, 11), " one │ two ");
+    }
+
+    #[test]
+    fn scrolls_to_the_selected_tab() {
+        let mut tabs = Tabs::new((0..5).map(|i| format!("tab{}", i)).collect());
+        tabs.select(4);
+        assert_eq!(render(&mut tabs, 15), "◂ tab3 │ tab4  ");
+    }
+}
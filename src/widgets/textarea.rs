@@ -0,0 +1,692 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::super::{Event, ExtendedKey, Key, Mouse, Rect, Surface};
+use unicode;
+
+use super::{key_of, mouse_in, CellStyle, Response, Widget};
+
+/// A place in the text, as line and byte offset within the line.
+pub type Position = (usize, usize);
+
+/// Most undo steps kept.
+const HISTORY: usize = 1000;
+
+/// Kinds of edits, consecutive typing being undone at once.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Edit {
+    Typing,
+    Other,
+}
+
+#[derive(Clone, Debug)]
+struct Snapshot {
+    lines: Vec<String>,
+    cursor: Position,
+}
+
+/// A line of the text as shown on screen, `start..end` of line `row`.
+#[derive(Clone, Copy, Debug)]
+struct Segment {
+    row: usize,
+    start: usize,
+    end: usize,
+    last: bool,
+}
+
+/// Multi-line editable text.
+///
+/// Arrows, Home, End, PageUp and PageDown move the cursor, with Shift held
+/// they select. Ctrl+A selects everything, Ctrl+C, Ctrl+X and Ctrl+V copy, cut
+/// and paste through a clipboard of the text area itself, Ctrl+Z and Ctrl+Y
+/// undo and redo. A click places the cursor and dragging selects.
+///
+/// ```
+/// use rustbox::{Event, ExtendedKey, Key, Modifiers};
+/// use rustbox::widgets::{TextArea, Widget};
+///
+/// let mut area = TextArea::new();
+/// area.set_text("first\nsecond");
+/// let shift = Modifiers::new_all(false, true, false);
+/// area.handle_event(&Event::KeyEvent(ExtendedKey::new(Key::Home, shift)));
+/// assert_eq!(area.selected_text(), Some("second".to_string()));
+///
+/// area.handle_event(&Event::KeyEvent(ExtendedKey::new(Key::Char('2'), Modifiers::new())));
+/// assert_eq!(area.text(), "first\n2");
+///
+/// let ctrl = Modifiers::new_all(true, false, false);
+/// area.handle_event(&Event::KeyEvent(ExtendedKey::new(Key::Char('z'), ctrl)));
+/// assert_eq!(area.text(), "first\nsecond");
+/// ```
+#[derive(Clone, Debug)]
+pub struct TextArea {
+    lines: Vec<String>,
+    cursor: Position,
+    // Other end of the selection
+    anchor: Option<Position>,
+    // Column kept when moving up and down through shorter lines
+    goal: Option<usize>,
+    clipboard: String,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    last_edit: Option<Edit>,
+    // First visible screen line, and columns scrolled off to the left without wrapping
+    scroll: usize,
+    hscroll: usize,
+    // Set when the cursor moved, so the next render scrolls it into view
+    reveal: bool,
+    dragging: bool,
+    /// Breaks lines longer than the width of the text area instead of
+    /// scrolling horizontally.
+    pub soft_wrap: bool,
+    /// Only a focused text area shows the terminal cursor.
+    pub focused: bool,
+    pub look: CellStyle,
+    pub selected_look: CellStyle,
+    area: Rect,
+}
+
+impl TextArea {
+    pub fn new() -> TextArea {
+        let look = CellStyle::default();
+        TextArea {
+            lines: vec![String::new()],
+            cursor: (0, 0),
+            anchor: None,
+            goal: None,
+            clipboard: String::new(),
+            undo: vec![],
+            redo: vec![],
+            last_edit: None,
+            scroll: 0,
+            hscroll: 0,
+            reveal: true,
+            dragging: false,
+            soft_wrap: true,
+            focused: true,
+            look,
+            selected_look: look.reversed(),
+            area: Rect::default(),
+        }
+    }
+
+    /// The text with lines joined by `\n`.
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    /// Replaces the text, moving the cursor to its end and forgetting the
+    /// undo history.
+    pub fn set_text(&mut self, text: &str) {
+        self.lines = text.split('\n').map(String::from).collect();
+        let last = self.lines.len() - 1;
+        self.cursor = (last, self.lines[last].len());
+        self.anchor = None;
+        self.undo.clear();
+        self.redo.clear();
+        self.last_edit = None;
+        self.reveal = true;
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn cursor(&self) -> Position {
+        self.cursor
+    }
+
+    /// Moves the cursor, dropping the selection.
+    pub fn set_cursor(&mut self, position: Position) {
+        let position = self.clamp(position);
+        self.move_to(position, false);
+    }
+
+    /// Start and end of the selection, if anything is selected.
+    pub fn selection(&self) -> Option<(Position, Position)> {
+        match self.anchor {
+            Some(anchor) if anchor < self.cursor => Some((anchor, self.cursor)),
+            Some(anchor) if anchor > self.cursor => Some((self.cursor, anchor)),
+            _ => None,
+        }
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        self.selection().map(|(start, end)| self.slice(start, end))
+    }
+
+    /// Text copied or cut last.
+    pub fn clipboard(&self) -> &str {
+        &self.clipboard
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn undo(&mut self) -> bool {
+        match self.undo.pop() {
+            Some(snapshot) => {
+                let current = self.snapshot();
+                self.redo.push(current);
+                self.restore(snapshot);
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn redo(&mut self) -> bool {
+        match self.redo.pop() {
+            Some(snapshot) => {
+                let current = self.snapshot();
+                self.undo.push(current);
+                self.restore(snapshot);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Inserts `text` at the cursor, replacing the selection.
+    pub fn insert(&mut self, text: &str) {
+        self.checkpoint(Edit::Other);
+        self.replace_selection(text);
+    }
+
+    pub fn copy(&mut self) {
+        if let Some(text) = self.selected_text() {
+            self.clipboard = text;
+        }
+    }
+
+    pub fn cut(&mut self) {
+        if let Some(text) = self.selected_text() {
+            self.clipboard = text;
+            self.checkpoint(Edit::Other);
+            self.replace_selection("");
+        }
+    }
+
+    pub fn paste(&mut self) {
+        let text = self.clipboard.clone();
+        self.insert(&text);
+    }
+
+    pub fn select_all(&mut self) {
+        let last = self.lines.len() - 1;
+        self.anchor = Some((0, 0));
+        self.cursor = (last, self.lines[last].len());
+        self.after_move();
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot { lines: self.lines.clone(), cursor: self.cursor }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.lines = snapshot.lines;
+        self.cursor = snapshot.cursor;
+        self.anchor = None;
+        self.last_edit = None;
+        self.after_move();
+    }
+
+    /// Remembers the text before an edit, unless it continues the typing of
+    /// the last one.
+    fn checkpoint(&mut self, edit: Edit) {
+        if edit == Edit::Typing && self.last_edit == Some(Edit::Typing) {
+            return;
+        }
+        let snapshot = self.snapshot();
+        self.undo.push(snapshot);
+        if self.undo.len() > HISTORY {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+        self.last_edit = Some(edit);
+    }
+
+    fn clamp(&self, (row, col): Position) -> Position {
+        let row = row.min(self.lines.len() - 1);
+        let line = &self.lines[row];
+        let col = line.grapheme_indices(true)
+            .map(|(i, _)| i)
+            .chain(Some(line.len()))
+            .take_while(|&i| i <= col)
+            .last()
+            .unwrap_or(0);
+        (row, col)
+    }
+
+    fn slice(&self, start: Position, end: Position) -> String {
+        if start.0 == end.0 {
+            return self.lines[start.0][start.1..end.1].to_string();
+        }
+        let mut text = self.lines[start.0][start.1..].to_string();
+        for line in &self.lines[start.0 + 1..end.0] {
+            text.push('\n');
+            text.push_str(line);
+        }
+        text.push('\n');
+        text.push_str(&self.lines[end.0][..end.1]);
+        text
+    }
+
+    /// Replaces the text between `start` and `end`, returning the position
+    /// after the new text.
+    fn replace(&mut self, start: Position, end: Position, text: &str) -> Position {
+        let head = self.lines[start.0][..start.1].to_string();
+        let tail = self.lines[end.0][end.1..].to_string();
+        let mut new: Vec<String> = text.split('\n').map(String::from).collect();
+        let last = new.len() - 1;
+        let position = (start.0 + last, if last == 0 { start.1 + new[0].len() } else { new[last].len() });
+        new[0].insert_str(0, &head);
+        new[last].push_str(&tail);
+        self.lines.splice(start.0..end.0 + 1, new);
+        position
+    }
+
+    fn replace_selection(&mut self, text: &str) {
+        let (start, end) = self.selection().unwrap_or((self.cursor, self.cursor));
+        self.cursor = self.replace(start, end, text);
+        self.anchor = None;
+        self.after_move();
+    }
+
+    /// Deletes the selection, or the text between the cursor and `to`.
+    fn delete_towards(&mut self, to: Position) {
+        if self.selection().is_none() {
+            if to == self.cursor {
+                return;
+            }
+            self.anchor = Some(to);
+        }
+        self.checkpoint(Edit::Other);
+        self.replace_selection("");
+    }
+
+    fn after_move(&mut self) {
+        self.reveal = true;
+    }
+
+    fn move_to(&mut self, position: Position, extend: bool) {
+        if extend {
+            if self.anchor.is_none() {
+                self.anchor = Some(self.cursor);
+            }
+        } else {
+            self.anchor = None;
+        }
+        self.cursor = position;
+        self.last_edit = None;
+        self.after_move();
+    }
+
+    fn previous_position(&self) -> Position {
+        let (row, col) = self.cursor;
+        match self.lines[row][..col].grapheme_indices(true).next_back() {
+            Some((i, _)) => (row, i),
+            None if row > 0 => (row - 1, self.lines[row - 1].len()),
+            None => (row, col),
+        }
+    }
+
+    fn next_position(&self) -> Position {
+        let (row, col) = self.cursor;
+        match self.lines[row][col..].graphemes(true).next() {
+            Some(grapheme) => (row, col + grapheme.len()),
+            None if row + 1 < self.lines.len() => (row + 1, 0),
+            None => (row, col),
+        }
+    }
+
+    /// The lines as shown on screen, wrapped to the width of the text area.
+    fn segments(&self) -> Vec<Segment> {
+        let width = if self.soft_wrap && self.area.width > 0 { self.area.width } else { usize::MAX };
+        let mut segments = vec![];
+        for (row, line) in self.lines.iter().enumerate() {
+            let mut start = 0;
+            let mut columns = 0;
+            for (i, grapheme) in line.grapheme_indices(true) {
+                let w = unicode::grapheme_width(grapheme);
+                if columns + w > width && i > start {
+                    segments.push(Segment { row, start, end: i, last: false });
+                    start = i;
+                    columns = 0;
+                }
+                columns += w;
+            }
+            segments.push(Segment { row, start, end: line.len(), last: true });
+        }
+        segments
+    }
+
+    /// Screen line and column of `position`.
+    fn visual(&self, segments: &[Segment], (row, col): Position) -> (usize, usize) {
+        for (i, segment) in segments.iter().enumerate() {
+            if segment.row == row && col >= segment.start && (col < segment.end || segment.last) {
+                return (i, unicode::text_width(&self.lines[row][segment.start..col]));
+            }
+        }
+        (0, 0)
+    }
+
+    /// The position shown closest to column `x` of screen line `line`.
+    fn position_at(&self, segments: &[Segment], line: usize, x: usize) -> Position {
+        let segment = segments[line.min(segments.len() - 1)];
+        let text = &self.lines[segment.row][segment.start..segment.end];
+        let mut columns = 0;
+        let mut col = segment.start;
+        for (grapheme, w) in unicode::graphemes(text) {
+            if columns + w > x {
+                return (segment.row, col);
+            }
+            columns += w;
+            col += grapheme.len();
+        }
+        if !segment.last {
+            // the end of a wrapped line is shown at the start of the next one
+            return (segment.row, self.lines[segment.row][..col].grapheme_indices(true)
+                    .next_back().map(|(i, _)| i).unwrap_or(segment.start));
+        }
+        (segment.row, col)
+    }
+
+    /// Moves up or down by `lines` screen lines, keeping to the goal column.
+    fn move_vertically(&mut self, lines: isize, extend: bool) {
+        let segments = self.segments();
+        let (line, x) = self.visual(&segments, self.cursor);
+        let goal = self.goal.unwrap_or(x);
+        let target = if lines < 0 {
+            line.saturating_sub(-lines as usize)
+        } else {
+            (line + lines as usize).min(segments.len() - 1)
+        };
+        let position = if target == line && lines < 0 {
+            (self.cursor.0, 0)
+        } else if target == line && lines > 0 {
+            (self.cursor.0, self.lines[self.cursor.0].len())
+        } else {
+            self.position_at(&segments, target, goal)
+        };
+        self.move_to(position, extend);
+        self.goal = Some(goal);
+    }
+
+    fn handle_key(&mut self, key: &ExtendedKey) -> Response {
+        let shift = key.modifiers.shift;
+        let page = self.area.height.max(1) as isize;
+        if key.modifiers.ctrl {
+            self.goal = None;
+            match key.key {
+                Key::Char('a') => self.select_all(),
+                Key::Char('c') => self.copy(),
+                Key::Char('x') => self.cut(),
+                Key::Char('v') => self.paste(),
+                Key::Char('z') => { self.undo(); },
+                Key::Char('y') => { self.redo(); },
+                Key::Home => self.move_to((0, 0), shift),
+                Key::End => {
+                    let last = self.lines.len() - 1;
+                    let end = (last, self.lines[last].len());
+                    self.move_to(end, shift);
+                },
+                _ => return Response::Ignored,
+            }
+            return Response::Handled;
+        }
+
+        let goal = self.goal.take();
+        match key.key {
+            Key::Char(_) if key.modifiers.alt => return Response::Ignored,
+            Key::Char(c) => {
+                // typing over a selection is undone on its own
+                let edit = if self.selection().is_some() { Edit::Other } else { Edit::Typing };
+                self.checkpoint(edit);
+                self.replace_selection(&c.to_string());
+                self.last_edit = Some(Edit::Typing);
+            },
+            Key::Enter => self.insert("\n"),
+            Key::Backspace => {
+                let to = self.previous_position();
+                self.delete_towards(to);
+            },
+            Key::Delete => {
+                let to = self.next_position();
+                self.delete_towards(to);
+            },
+            Key::Left => {
+                let to = self.previous_position();
+                self.move_to(to, shift);
+            },
+            Key::Right => {
+                let to = self.next_position();
+                self.move_to(to, shift);
+            },
+            Key::Up | Key::Down | Key::PageUp | Key::PageDown => {
+                self.goal = goal;
+                let lines = match key.key {
+                    Key::Up => -1,
+                    Key::Down => 1,
+                    Key::PageUp => -page,
+                    _ => page,
+                };
+                self.move_vertically(lines, shift);
+            },
+            Key::Home => {
+                let segments = self.segments();
+                let (line, _) = self.visual(&segments, self.cursor);
+                let start = (self.cursor.0, segments[line].start);
+                self.move_to(start, shift);
+            },
+            Key::End => {
+                let segments = self.segments();
+                let (line, _) = self.visual(&segments, self.cursor);
+                let end = self.position_at(&segments, line, usize::MAX);
+                self.move_to(end, shift);
+            },
+            _ => return Response::Ignored,
+        }
+        Response::Handled
+    }
+
+    fn handle_mouse(&mut self, mouse: Mouse, x: usize, y: usize) -> Response {
+        match mouse {
+            Mouse::Drag if !self.dragging => return Response::Ignored,
+            Mouse::Left | Mouse::Drag => {
+                let segments = self.segments();
+                let position = self.position_at(&segments, self.scroll + y, self.hscroll + x);
+                // a press places the cursor, moving with the button held selects
+                let dragging = mouse == Mouse::Drag;
+                self.move_to(position, dragging);
+                if !dragging {
+                    self.anchor = Some(position);
+                }
+                self.dragging = true;
+                self.goal = None;
+            },
+            Mouse::Release => self.dragging = false,
+            Mouse::WheelUp => self.scroll = self.scroll.saturating_sub(1),
+            Mouse::WheelDown => {
+                let lines = self.segments().len();
+                self.scroll = (self.scroll + 1).min(lines.saturating_sub(self.area.height));
+            },
+            _ => return Response::Ignored,
+        }
+        Response::Handled
+    }
+}
+
+impl Default for TextArea {
+    fn default() -> Self {
+        TextArea::new()
+    }
+}
+
+impl Widget for TextArea {
+    fn render(&mut self, surface: &Surface) {
+        self.area = surface.area();
+        let (width, height) = (surface.width(), surface.height());
+        let segments = self.segments();
+        let (line, x) = self.visual(&segments, self.cursor);
+        if self.reveal {
+            self.reveal = false;
+            if line < self.scroll {
+                self.scroll = line;
+            } else if height > 0 && line >= self.scroll + height {
+                self.scroll = line + 1 - height;
+            }
+            if self.soft_wrap {
+                self.hscroll = 0;
+            } else if x < self.hscroll {
+                self.hscroll = x;
+            } else if width > 0 && x >= self.hscroll + width {
+                self.hscroll = x + 1 - width;
+            }
+        }
+
+        let look = self.look;
+        surface.fill(look.style, look.fg, look.bg, ' ');
+        let selection = self.selection();
+        for (y, segment) in segments.iter().skip(self.scroll).take(height).enumerate() {
+            let line = &self.lines[segment.row];
+            let mut column = 0;
+            let mut col = segment.start;
+            for (grapheme, w) in unicode::graphemes(&line[segment.start..segment.end]) {
+                if column >= self.hscroll && column + w <= self.hscroll + width {
+                    let position = (segment.row, col);
+                    let look = match selection {
+                        Some((start, end)) if position >= start && position < end => self.selected_look,
+                        _ => self.look,
+                    };
+                    surface.print(column - self.hscroll, y, look.style, look.fg, look.bg, grapheme);
+                }
+                column += w;
+                col += grapheme.len();
+            }
+        }
+
+        if self.focused && line >= self.scroll && x >= self.hscroll {
+            surface.set_cursor((x - self.hscroll) as isize, (line - self.scroll) as isize);
+        } else if self.focused {
+            surface.set_cursor(-1, -1);
+        }
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Response {
+        if let Some(key) = key_of(event) {
+            return self.handle_key(&key);
+        }
+        if let Event::MouseEvent(Mouse::Release, _, _) = *event {
+            // also when released outside of the text area
+            self.dragging = false;
+        }
+        match mouse_in(event, self.area) {
+            Some((mouse, x, y)) => self.handle_mouse(mouse, x, y),
+            None => Response::Ignored,
+        }
+    }
+
+    fn desired_size(&self) -> (usize, usize) {
+        let width = self.lines.iter().map(|line| unicode::text_width(line)).max().unwrap_or(0);
+        (width + 1, self.lines.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextArea;
+    use super::super::Widget;
+    use {Event, ExtendedKey, Key, Modifiers};
+
+    fn press(area: &mut TextArea, key: Key) {
+        area.handle_event(&Event::KeyEvent(ExtendedKey::new(key, Modifiers::new())));
+    }
+
+    fn type_text(area: &mut TextArea, text: &str) {
+        for c in text.chars() {
+            match c {
+                '\n' => press(area, Key::Enter),
+                c => press(area, Key::Char(c)),
+            }
+        }
+    }
+
+    #[test]
+    fn undo_across_a_newline() {
+        let mut area = TextArea::new();
+        type_text(&mut area, "ab\ncd");
+        assert_eq!(area.text(), "ab\ncd");
+        assert!(area.undo());
+        assert_eq!((area.text(), area.cursor()), ("ab\n".to_string(), (1, 0)));
+        assert!(area.undo());
+        assert_eq!((area.text(), area.cursor()), ("ab".to_string(), (0, 2)));
+        assert!(area.undo());
+        assert_eq!(area.text(), "");
+        assert!(!area.undo());
+
+        // joining two lines is undone in one step
+        area.set_text("ab\ncd");
+        area.set_cursor((1, 0));
+        press(&mut area, Key::Backspace);
+        assert_eq!((area.text(), area.cursor()), ("abcd".to_string(), (0, 2)));
+        assert!(area.undo());
+        assert_eq!((area.text(), area.cursor()), ("ab\ncd".to_string(), (1, 0)));
+    }
+
+    #[test]
+    fn a_new_edit_clears_redo() {
+        let mut area = TextArea::new();
+        type_text(&mut area, "one\ntwo");
+        area.undo();
+        assert!(area.can_redo());
+        assert!(area.redo());
+        assert_eq!(area.text(), "one\ntwo");
+
+        area.undo();
+        area.undo();
+        assert_eq!(area.text(), "one");
+        type_text(&mut area, "!");
+        assert!(!area.can_redo());
+        assert!(!area.redo());
+        assert_eq!(area.text(), "one!");
+        assert!(area.undo());
+        assert_eq!(area.text(), "one");
+    }
+
+    #[test]
+    fn cursor_moves_over_wide_characters() {
+        let mut area = TextArea::new();
+        area.set_text("a日本b\nxyzwv");
+        area.set_cursor((0, 0));
+        let mut positions = vec![];
+        for _ in 0..4 {
+            press(&mut area, Key::Right);
+            positions.push(area.cursor());
+        }
+        assert_eq!(positions, vec![(0, 1), (0, 4), (0, 7), (0, 8)]);
+        press(&mut area, Key::Left);
+        assert_eq!(area.cursor(), (0, 7));
+
+        // moving down keeps the column, moving back up lands on the character there
+        press(&mut area, Key::Down);
+        assert_eq!(area.cursor(), (1, 5));
+        press(&mut area, Key::Left);
+        press(&mut area, Key::Left);
+        assert_eq!(area.cursor(), (1, 3));
+        press(&mut area, Key::Up);
+        assert_eq!(area.cursor(), (0, 4));
+
+        // a character with a combining mark is a single step
+        area.set_text("e\u{301}x");
+        area.set_cursor((0, 0));
+        press(&mut area, Key::Right);
+        assert_eq!(area.cursor(), (0, 3));
+        press(&mut area, Key::Backspace);
+        assert_eq!(area.text(), "x");
+    }
+}
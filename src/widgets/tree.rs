@@ -0,0 +1,470 @@
+use super::super::{Border, Event, Key, Mouse, Rect, Surface};
+use border;
+use unicode;
+
+use super::selection::Selection;
+use super::{key_of, mouse_in, CellStyle, Response, Widget};
+
+/// Columns each level of the tree is indented by.
+const INDENT: usize = 3;
+
+/// An entry of a `Tree` with the entries below it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeNode {
+    pub label: String,
+    pub children: Vec<TreeNode>,
+    pub expanded: bool,
+    /// Set for nodes whose children are only loaded when they are expanded,
+    /// see `Tree::set_loader`.
+    pub lazy: bool,
+}
+
+impl TreeNode {
+    /// A node without children.
+    pub fn new<S: Into<String>>(label: S) -> TreeNode {
+        TreeNode {
+            label: label.into(),
+            children: vec![],
+            expanded: false,
+            lazy: false,
+        }
+    }
+
+    pub fn with_children<S: Into<String>>(label: S, children: Vec<TreeNode>) -> TreeNode {
+        TreeNode { children, ..TreeNode::new(label) }
+    }
+
+    /// A node whose children are loaded when it is first expanded.
+    pub fn lazy<S: Into<String>>(label: S) -> TreeNode {
+        TreeNode { lazy: true, ..TreeNode::new(label) }
+    }
+
+    /// Whether the node has, or may have, children.
+    pub fn is_expandable(&self) -> bool {
+        self.lazy || !self.children.is_empty()
+    }
+}
+
+/// A node as shown on screen.
+struct Row {
+    path: Vec<usize>,
+    // Whether the node and each of its ancestors is the last of its siblings,
+    // from the top level down
+    last: Vec<bool>,
+}
+
+impl Row {
+    fn depth(&self) -> usize {
+        self.path.len() - 1
+    }
+
+    /// Column of the expander glyph.
+    fn expander(&self) -> usize {
+        self.depth() * INDENT
+    }
+}
+
+/// Loads the children of a lazy node, given its path and the node itself.
+pub type Loader = Box<dyn FnMut(&[usize], &TreeNode) -> Vec<TreeNode>>;
+
+/// Nodes shown with their children indented below them.
+///
+/// Up and Down move the selection, Right expands a node or moves to its first
+/// child, Left collapses it or moves to its parent, and Enter toggles it.
+/// Clicking the expander in front of a node also toggles it. Nodes are
+/// addressed by paths of child indices, starting with the index of the top
+/// level node.
+///
+/// ```
+/// use rustbox::RustBox;
+/// use rustbox::backend::HeadlessBackend;
+/// use rustbox::widgets::{Tree, TreeNode, Widget};
+/// use std::default::Default;
+///
+/// let backend = HeadlessBackend::new(12, 4);
+/// let screen = backend.screen();
+/// let rb = RustBox::init_with_backend(Box::new(backend), Default::default()).unwrap();
+///
+/// let mut tree = Tree::new(vec![TreeNode::lazy("src")]);
+/// tree.set_loader(|_, _| vec![TreeNode::new("lib.rs"), TreeNode::new("main.rs")]);
+/// tree.expand(&[0]);
+/// tree.render(&rb.surface());
+/// rb.present();
+///
+/// assert_eq!(screen.lines(), vec!["▾ src       ", "├─   lib.rs ", "└─   main.rs", "            "]);
+/// ```
+pub struct Tree {
+    roots: Vec<TreeNode>,
+    loader: Option<Loader>,
+    /// Draws the indentation guides, ASCII also swaps the expanders for `+` and `-`.
+    pub border: Border,
+    pub look: CellStyle,
+    pub selected_look: CellStyle,
+    pub guide_look: CellStyle,
+    selection: Selection,
+    area: Rect,
+}
+
+impl Tree {
+    pub fn new(roots: Vec<TreeNode>) -> Tree {
+        let look = CellStyle::default();
+        let mut tree = Tree {
+            roots,
+            loader: None,
+            border: Border::Single,
+            look,
+            selected_look: look.reversed(),
+            guide_look: look,
+            selection: Selection::default(),
+            area: Rect::default(),
+        };
+        let len = tree.rows().len();
+        tree.selection.select(Some(0), len);
+        tree
+    }
+
+    pub fn roots(&self) -> &[TreeNode] {
+        &self.roots
+    }
+
+    /// Replaces the nodes, keeping the selected path selected if it still exists.
+    pub fn set_roots(&mut self, roots: Vec<TreeNode>) {
+        let selected = self.selected_path();
+        self.roots = roots;
+        self.reselect(selected);
+    }
+
+    /// Calls `loader` for the children of lazy nodes when they are expanded.
+    pub fn set_loader<F>(&mut self, loader: F)
+        where F: FnMut(&[usize], &TreeNode) -> Vec<TreeNode> + 'static
+    {
+        self.loader = Some(Box::new(loader));
+    }
+
+    pub fn node(&self, path: &[usize]) -> Option<&TreeNode> {
+        let (first, rest) = path.split_first()?;
+        let mut node = self.roots.get(*first)?;
+        for &i in rest {
+            node = node.children.get(i)?;
+        }
+        Some(node)
+    }
+
+    pub fn node_mut(&mut self, path: &[usize]) -> Option<&mut TreeNode> {
+        let (first, rest) = path.split_first()?;
+        let mut node = self.roots.get_mut(*first)?;
+        for &i in rest {
+            node = node.children.get_mut(i)?;
+        }
+        Some(node)
+    }
+
+    pub fn selected_path(&self) -> Option<Vec<usize>> {
+        let rows = self.rows();
+        self.selection.selected.and_then(|i| rows.into_iter().nth(i)).map(|row| row.path)
+    }
+
+    pub fn selected_node(&self) -> Option<&TreeNode> {
+        self.selected_path().and_then(|path| self.node(&path))
+    }
+
+    /// Selects the node at `path`, expanding its ancestors.
+    pub fn select(&mut self, path: &[usize]) {
+        for depth in 1..path.len() {
+            if let Some(node) = self.node_mut(&path[..depth]) {
+                node.expanded = true;
+            }
+        }
+        self.reselect(Some(path.to_vec()));
+    }
+
+    /// Expands the node at `path`, loading its children first if it is lazy.
+    pub fn expand(&mut self, path: &[usize]) {
+        let selected = self.selected_path();
+        let lazy = match self.node(path) {
+            Some(node) => node.lazy,
+            None => return,
+        };
+        if lazy {
+            let children = match (self.loader.take(), self.node(path)) {
+                (Some(mut loader), Some(node)) => {
+                    let children = loader(path, node);
+                    self.loader = Some(loader);
+                    children
+                },
+                (loader, _) => {
+                    self.loader = loader;
+                    vec![]
+                },
+            };
+            if let Some(node) = self.node_mut(path) {
+                node.children = children;
+                node.lazy = false;
+            }
+        }
+        if let Some(node) = self.node_mut(path) {
+            node.expanded = true;
+        }
+        self.reselect(selected);
+    }
+
+    /// Collapses the node at `path`, selecting it if one of its children was.
+    pub fn collapse(&mut self, path: &[usize]) {
+        let selected = self.selected_path();
+        if let Some(node) = self.node_mut(path) {
+            node.expanded = false;
+        }
+        let selected = selected.map(|selected| {
+            if selected.len() > path.len() && selected.starts_with(path) { path.to_vec() } else { selected }
+        });
+        self.reselect(selected);
+    }
+
+    pub fn toggle(&mut self, path: &[usize]) {
+        match self.node(path) {
+            Some(node) if node.expanded => self.collapse(path),
+            Some(_) => self.expand(path),
+            None => (),
+        }
+    }
+
+    /// The nodes shown, top level nodes and the children of expanded nodes.
+    fn rows(&self) -> Vec<Row> {
+        fn walk(nodes: &[TreeNode], path: &mut Vec<usize>, last: &mut Vec<bool>, rows: &mut Vec<Row>) {
+            for (i, node) in nodes.iter().enumerate() {
+                path.push(i);
+                last.push(i + 1 == nodes.len());
+                rows.push(Row { path: path.clone(), last: last.clone() });
+                if node.expanded {
+                    walk(&node.children, path, last, rows);
+                }
+                path.pop();
+                last.pop();
+            }
+        }
+        let mut rows = vec![];
+        walk(&self.roots, &mut vec![], &mut vec![], &mut rows);
+        rows
+    }
+
+    /// Selects the row showing `path`, or the closest one before it.
+    fn reselect(&mut self, path: Option<Vec<usize>>) {
+        let rows = self.rows();
+        let row = path.map(|path| rows.iter().rposition(|row| row.path <= path).unwrap_or(0));
+        self.selection.select(row.or(Some(0)), rows.len());
+    }
+
+    /// Indentation guides and the connector to the node.
+    fn guides(&self, row: &Row) -> String {
+        let mut guides = String::new();
+        if row.depth() == 0 {
+            return guides;
+        }
+        for &last in &row.last[1..row.depth()] {
+            if last {
+                guides.push_str("   ");
+            } else {
+                guides.push(self.border.glyph(border::VERTICAL));
+                guides.push_str("  ");
+            }
+        }
+        let connector = if row.last[row.depth()] { border::BOTTOM_LEFT } else { [true, true, true, false] };
+        guides.push(self.border.glyph(connector));
+        guides.push(self.border.glyph(border::HORIZONTAL));
+        guides.push(' ');
+        guides
+    }
+
+    fn expander(&self, node: &TreeNode) -> &'static str {
+        match (node.is_expandable(), node.expanded, self.border) {
+            (false, _, _) => "  ",
+            (true, true, Border::Ascii) => "- ",
+            (true, false, Border::Ascii) => "+ ",
+            (true, true, _) => "▾ ",
+            (true, false, _) => "▸ ",
+        }
+    }
+
+    fn handle_key(&mut self, event: &Event) -> Response {
+        let key = match key_of(event) {
+            Some(key) => key,
+            None => return Response::Ignored,
+        };
+        let path = match self.selected_path() {
+            Some(path) => path,
+            None => return Response::Ignored,
+        };
+        let (expandable, expanded) = match self.node(&path) {
+            Some(node) => (node.is_expandable(), node.expanded),
+            None => return Response::Ignored,
+        };
+        match key.key {
+            Key::Right if expandable && !expanded => self.expand(&path),
+            Key::Right if expanded => {
+                let mut child = path.clone();
+                child.push(0);
+                if self.node(&child).is_some() {
+                    self.reselect(Some(child));
+                }
+            },
+            Key::Left if expanded => self.collapse(&path),
+            Key::Left if path.len() > 1 => self.reselect(Some(path[..path.len() - 1].to_vec())),
+            Key::Enter if expandable => self.toggle(&path),
+            Key::Right | Key::Left => (),
+            _ => {
+                let rows = self.rows().len();
+                return self.selection.handle_key(&key, rows, self.area.height);
+            },
+        }
+        Response::Handled
+    }
+}
+
+impl Widget for Tree {
+    fn render(&mut self, surface: &Surface) {
+        self.area = surface.area();
+        let rows = self.rows();
+        self.selection.keep_visible(rows.len(), surface.height());
+
+        let width = surface.width();
+        let blank = " ".repeat(width);
+        let visible = rows.iter().enumerate().skip(self.selection.offset).take(surface.height());
+        for (y, (i, row)) in visible.enumerate() {
+            let node = match self.node(&row.path) {
+                Some(node) => node,
+                None => continue,
+            };
+            let look = if Some(i) == self.selection.selected { self.selected_look } else { self.look };
+            let guides = self.guides(row);
+            let guide = self.guide_look;
+            surface.print(0, y, self.look.style, self.look.fg, self.look.bg, &blank);
+            surface.print(0, y, guide.style, guide.fg, guide.bg, &guides);
+            let x = row.expander();
+            let text = format!("{}{}", self.expander(node), node.label);
+            surface.print_truncated(x, y, look.style, look.fg, look.bg, &text, width.saturating_sub(x),
+                                    Some("…"));
+        }
+    }
+
+    fn handle_event(&mut self, event: &Event) -> Response {
+        if key_of(event).is_some() {
+            return self.handle_key(event);
+        }
+        let rows = self.rows();
+        let height = self.area.height;
+        match mouse_in(event, self.area) {
+            Some((Mouse::Left, x, y)) if self.selection.offset + y < rows.len() => {
+                let index = self.selection.offset + y;
+                self.selection.select(Some(index), rows.len());
+                let row = &rows[index];
+                let on_expander = x >= row.expander() && x < row.expander() + 2;
+                if on_expander && self.node(&row.path).map_or(false, |node| node.is_expandable()) {
+                    self.toggle(&row.path);
+                }
+                Response::Handled
+            },
+            Some((Mouse::WheelUp, _, _)) => {
+                self.selection.scroll_by(-1, rows.len(), height);
+                Response::Handled
+            },
+            Some((Mouse::WheelDown, _, _)) => {
+                self.selection.scroll_by(1, rows.len(), height);
+                Response::Handled
+            },
+            _ => Response::Ignored,
+        }
+    }
+
+    fn desired_size(&self) -> (usize, usize) {
+        let rows = self.rows();
+        let width = rows.iter().filter_map(|row| {
+            self.node(&row.path).map(|node| row.expander() + 2 + unicode::text_width(&node.label))
+        }).max().unwrap_or(0);
+        (width, rows.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Tree, TreeNode};
+    use super::super::Widget;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use {Event, ExtendedKey, Key, Modifiers};
+
+    fn press(tree: &mut Tree, key: Key) {
+        tree.handle_event(&Event::KeyEvent(ExtendedKey::new(key, Modifiers::new())));
+    }
+
+    /// A tree of lazy directories, recording the paths it loads.
+    fn lazy_tree() -> (Tree, Rc<RefCell<Vec<Vec<usize>>>>) {
+        let loaded = Rc::new(RefCell::new(vec![]));
+        let mut tree = Tree::new(vec![TreeNode::lazy("a"), TreeNode::lazy("b")]);
+        let log = loaded.clone();
+        tree.set_loader(move |path, node| {
+            log.borrow_mut().push(path.to_vec());
+            match path.len() {
+                1 => vec![TreeNode::lazy(format!("{}1", node.label)), TreeNode::new(format!("{}2", node.label))],
+                _ => vec![TreeNode::new(format!("{}x", node.label))],
+            }
+        });
+        (tree, loaded)
+    }
+
+    #[test]
+    fn children_are_loaded_once() {
+        let (mut tree, loaded) = lazy_tree();
+        assert!(tree.roots()[0].children.is_empty());
+        tree.expand(&[0]);
+        assert_eq!(tree.node(&[0, 1]).unwrap().label, "a2");
+        tree.collapse(&[0]);
+        tree.expand(&[0]);
+        tree.expand(&[0, 0]);
+        assert_eq!(tree.node(&[0, 0, 0]).unwrap().label, "a1x");
+        assert_eq!(*loaded.borrow(), vec![vec![0], vec![0, 0]]);
+    }
+
+    #[test]
+    fn lazy_nodes_that_turn_out_empty() {
+        let mut tree = Tree::new(vec![TreeNode::lazy("empty"), TreeNode::lazy("unloaded")]);
+        tree.set_loader(|_, _| vec![]);
+        assert!(tree.roots()[0].is_expandable());
+        press(&mut tree, Key::Right);
+        assert!(!tree.roots()[0].is_expandable());
+        // nothing to move into
+        press(&mut tree, Key::Right);
+        assert_eq!(tree.selected_path(), Some(vec![0]));
+
+        // without a loader a lazy node just has no children
+        let mut tree = Tree::new(vec![TreeNode::lazy("x")]);
+        tree.expand(&[0]);
+        assert!(tree.roots()[0].expanded && tree.roots()[0].children.is_empty());
+    }
+
+    #[test]
+    fn collapsing_selects_the_node_of_a_hidden_child() {
+        let (mut tree, _) = lazy_tree();
+        tree.expand(&[0]);
+        tree.expand(&[0, 0]);
+        tree.select(&[0, 0, 0]);
+        assert_eq!(tree.selected_node().unwrap().label, "a1x");
+
+        tree.collapse(&[0]);
+        assert_eq!(tree.selected_path(), Some(vec![0]));
+        // the rows after the collapsed node are still reachable
+        press(&mut tree, Key::Down);
+        assert_eq!(tree.selected_path(), Some(vec![1]));
+
+        // collapsing another node keeps the selection
+        tree.expand(&[0]);
+        tree.select(&[0, 1]);
+        tree.expand(&[1]);
+        tree.collapse(&[1]);
+        assert_eq!(tree.selected_path(), Some(vec![0, 1]));
+        // Left on a child moves to its parent, then collapses it
+        press(&mut tree, Key::Left);
+        assert_eq!(tree.selected_path(), Some(vec![0]));
+        press(&mut tree, Key::Left);
+        assert!(!tree.roots()[0].expanded);
+    }
+}